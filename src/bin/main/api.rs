@@ -0,0 +1,413 @@
+use super::{RunResult, ScheduledJob};
+
+use chrono::{DateTime, Utc};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::Serialize;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+
+/// APIから参照する実行中のジョブ．設定の再読み込みの度に入れ替える．
+pub type SharedJobs = Arc<RwLock<Vec<ScheduledJob>>>;
+
+/// APIへのリクエストの制限
+#[derive(Debug, Clone)]
+pub struct Access {
+    /// 待ち受けているアドレス
+    addr: SocketAddr,
+    /// 要求するBearerトークン
+    token: Option<String>,
+}
+
+/// トークンを比較する．一致する長さから推測されないよう，比較の時間は内容によらない．
+fn token_matches(bearer: &str, token: &str) -> bool {
+    bearer.len() == token.len()
+        && bearer
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+impl Access {
+    pub fn new(addr: SocketAddr, token: Option<String>) -> Self {
+        Self { addr, token }
+    }
+
+    /// DNSリバインディングを防ぐため，Hostが待ち受けているアドレスであるかを確かめる．
+    /// 全てのアドレスで待ち受けている場合は特定できないため，トークンによる認証に委ねる．
+    fn allows_host(&self, host: &str) -> bool {
+        if self.addr.ip().is_unspecified() {
+            return true;
+        }
+        host == self.addr.to_string()
+            || (self.addr.ip().is_loopback() && host == format!("localhost:{}", self.addr.port()))
+    }
+
+    /// リクエストを確かめ，拒否する場合はその応答を返す．
+    /// ブラウザーからの他のオリジンのリクエストは拒否し，トークンを指定した場合は`/healthz`以外に要求する．
+    fn check(&self, request: &Request<Body>) -> Result<(), (StatusCode, &'static str)> {
+        let header = |name| {
+            request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+
+        let host = header(hyper::header::HOST).unwrap_or_default();
+        if !self.allows_host(host) {
+            return Err((StatusCode::FORBIDDEN, "Unexpected host."));
+        }
+        if let Some(origin) = header(hyper::header::ORIGIN) {
+            if origin != format!("http://{}", host) {
+                return Err((
+                    StatusCode::FORBIDDEN,
+                    "Cross-origin requests are not allowed.",
+                ));
+            }
+        }
+
+        if let Some(token) = self.token.as_ref() {
+            let authorized = header(hyper::header::AUTHORIZATION)
+                .and_then(|authorization| authorization.strip_prefix("Bearer "))
+                .is_some_and(|bearer| token_matches(bearer, token));
+            if !authorized && request.uri().path().trim_matches('/') != "healthz" {
+                return Err((StatusCode::UNAUTHORIZED, "Invalid token."));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// ジョブの状態
+#[derive(Debug, Serialize)]
+struct JobView<'a> {
+    name: &'a str,
+    cron: &'a str,
+    timezone: String,
+    paused: bool,
+    running: bool,
+    last_run: Option<DateTime<Utc>>,
+    last_result: Option<RunResult>,
+    next_run: Option<DateTime<Utc>>,
+}
+
+impl<'a> JobView<'a> {
+    fn new(job: &'a ScheduledJob) -> Self {
+        let status = job.handle.status();
+        Self {
+            name: &job.config.search_config.name,
+            cron: &job.config.cron,
+            timezone: job.config.timezone.unwrap_or(chrono_tz::UTC).to_string(),
+            paused: job.handle.is_paused(),
+            running: job.handle.is_running(),
+            last_run: status.last_run,
+            last_result: status.last_result,
+            next_run: status.next_run,
+        }
+    }
+}
+
+fn json<T: Serialize>(status: StatusCode, value: &T) -> Response<Body> {
+    match serde_json::to_vec_pretty(value) {
+        Ok(body) => Response::builder()
+            .status(status)
+            .header(hyper::header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .expect("response is valid"),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    json(status, &serde_json::json!({ "error": message }))
+}
+
+/// リクエストに応答する．
+///
+/// - `GET /healthz`
+/// - `GET /metrics`: Prometheusのテキスト形式のメトリクス
+/// - `GET /jobs`, `GET /jobs/{name}`: 最後の実行日時・結果と次回の実行日時
+/// - `GET /jobs/{name}/posts`: 最後に取得したポスト
+/// - `POST /jobs/{name}/trigger`: 直ちに実行する
+/// - `POST /jobs/{name}/pause`, `POST /jobs/{name}/resume`: スケジュールによる実行の一時停止・再開
+pub fn handle(request: &Request<Body>, jobs: &SharedJobs, access: &Access) -> Response<Body> {
+    if let Err((status, message)) = access.check(request) {
+        return error(status, message);
+    }
+
+    let segments = request
+        .uri()
+        .path()
+        .trim_matches('/')
+        .split('/')
+        .map(|segment| percent_encoding::percent_decode_str(segment).decode_utf8_lossy())
+        .collect::<Vec<_>>();
+    let segments = segments.iter().map(AsRef::as_ref).collect::<Vec<&str>>();
+    let jobs = jobs.read().unwrap();
+
+    match (request.method(), segments.as_slice()) {
+        (&Method::GET, ["healthz"]) => json(StatusCode::OK, &serde_json::json!({ "status": "ok" })),
+        (&Method::GET, ["metrics"]) => Response::builder()
+            .header(
+                hyper::header::CONTENT_TYPE,
+                "text/plain; version=0.0.4; charset=utf-8",
+            )
+            .body(Body::from(
+                search_and_report::metrics::global().render(Utc::now()),
+            ))
+            .expect("response is valid"),
+        (&Method::GET, ["jobs"]) => json(
+            StatusCode::OK,
+            &jobs.iter().map(JobView::new).collect::<Vec<_>>(),
+        ),
+        (method, ["jobs", name, action @ ..]) => {
+            let Some(job) = jobs
+                .iter()
+                .find(|job| job.config.search_config.name == *name)
+            else {
+                return error(StatusCode::NOT_FOUND, &format!("Unknown job: {}.", name));
+            };
+
+            match (method, action) {
+                (&Method::GET, []) => json(StatusCode::OK, &JobView::new(job)),
+                (&Method::GET, ["posts"]) => json(StatusCode::OK, &job.handle.status().last_posts),
+                (&Method::POST, ["trigger"]) => {
+                    job.trigger();
+                    json(StatusCode::ACCEPTED, &JobView::new(job))
+                }
+                (&Method::POST, ["pause"]) => {
+                    job.handle.pause();
+                    json(StatusCode::OK, &JobView::new(job))
+                }
+                (&Method::POST, ["resume"]) => {
+                    job.handle.resume();
+                    json(StatusCode::OK, &JobView::new(job))
+                }
+                (_, [] | ["posts" | "trigger" | "pause" | "resume"]) => {
+                    error(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed.")
+                }
+                _ => error(StatusCode::NOT_FOUND, "Not found."),
+            }
+        }
+        (_, ["healthz"] | ["metrics"] | ["jobs"]) => {
+            error(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed.")
+        }
+        _ => error(StatusCode::NOT_FOUND, "Not found."),
+    }
+}
+
+/// `addr`でリクエストを受け付けるサーバーを作製する．`shutdown`が完了すると終了する．
+pub fn serve<F: Future<Output = ()>>(
+    addr: SocketAddr,
+    token: Option<String>,
+    jobs: SharedJobs,
+    shutdown: F,
+) -> Result<impl Future<Output = Result<(), hyper::Error>>, hyper::Error> {
+    if !addr.ip().is_loopback() && token.is_none() {
+        tracing::warn!(
+            "The api on {} is reachable from other hosts without authentication. Set api_token in the config file.",
+            addr
+        );
+    }
+
+    let access = Access::new(addr, token);
+    let make_service = make_service_fn(move |_connection| {
+        let jobs = Arc::clone(&jobs);
+        let access = access.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let response = handle(&request, &jobs, &access);
+                async move { Ok::<_, Infallible>(response) }
+            }))
+        }
+    });
+
+    Ok(hyper::Server::try_bind(&addr)?
+        .serve(make_service)
+        .with_graceful_shutdown(shutdown))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{handle, Access, SharedJobs};
+    use crate::config::AllConfig;
+    use crate::schedule_and_run_app;
+    use crate::test::job_config;
+    use search_and_report::clock::{Clock, FixedClock};
+
+    use chrono::{TimeZone, Utc};
+    use hyper::{Body, Method, Request, StatusCode};
+    use std::sync::{Arc, RwLock};
+
+    /// 待ち受けているアドレス
+    const ADDR: &str = "127.0.0.1:8080";
+
+    async fn request(
+        jobs: &SharedJobs,
+        method: Method,
+        uri: &str,
+    ) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("host", ADDR)
+            .body(Body::empty())
+            .unwrap();
+        let response = handle(&request, jobs, &Access::new(ADDR.parse().unwrap(), None));
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_handle() {
+        let clock: Arc<dyn Clock> = Arc::new(FixedClock::new(
+            Utc.with_ymd_and_hms(2023, 11, 27, 0, 0, 0).unwrap(),
+        ));
+        let config = AllConfig {
+            include: Vec::new(),
+            max_concurrent_jobs: None,
+            api_token: None,
+            search_and_reports: vec![
+                job_config("Rust", "0 0 6 * * * *"),
+                job_config("Go CLI", "0 0 12 * * * *"),
+            ],
+        };
+        let (_scheduler, jobs) = schedule_and_run_app(config, &None, clock).await.unwrap();
+        let jobs: SharedJobs = Arc::new(RwLock::new(jobs));
+
+        let (status, body) = request(&jobs, Method::GET, "/healthz").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "ok");
+
+        let (status, body) = request(&jobs, Method::GET, "/jobs").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body[0]["name"], "rust");
+        assert_eq!(body[0]["next_run"], "2023-11-27T06:00:00Z");
+        assert_eq!(body[0]["last_run"], serde_json::Value::Null);
+        assert_eq!(body[1]["name"], "go cli");
+
+        // 名前はパーセントエンコードする
+        let (status, body) = request(&jobs, Method::POST, "/jobs/go%20cli/pause").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["paused"], true);
+        assert!(jobs.read().unwrap()[1].handle.is_paused());
+        let (_, body) = request(&jobs, Method::POST, "/jobs/go%20cli/resume").await;
+        assert_eq!(body["paused"], false);
+
+        let (status, body) = request(&jobs, Method::GET, "/jobs/rust/posts").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!([]));
+
+        let (status, _) = request(&jobs, Method::GET, "/jobs/java").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = request(&jobs, Method::GET, "/jobs/rust/trigger").await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        let (status, _) = request(&jobs, Method::GET, "/logs").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let response = handle(
+            &Request::builder()
+                .uri("/metrics")
+                .header("host", ADDR)
+                .body(Body::empty())
+                .unwrap(),
+            &jobs,
+            &Access::new(ADDR.parse().unwrap(), None),
+        );
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        assert!(String::from_utf8_lossy(&body)
+            .contains("# TYPE search_and_report_requests_total counter"));
+    }
+
+    #[tokio::test]
+    async fn test_access() {
+        let jobs = SharedJobs::default();
+        let status = |access: &Access, method: Method, uri: &str, headers: &[(&str, &str)]| {
+            let mut request = Request::builder().method(method).uri(uri);
+            for (name, value) in headers.iter() {
+                request = request.header(*name, *value);
+            }
+            handle(&request.body(Body::empty()).unwrap(), &jobs, access).status()
+        };
+
+        let access = Access::new(ADDR.parse().unwrap(), None);
+        let local = [("host", "localhost:8080")];
+        assert_eq!(
+            status(&access, Method::GET, "/jobs", &local),
+            StatusCode::OK
+        );
+        // DNSリバインディングによる他のホスト名は拒否する
+        assert_eq!(
+            status(
+                &access,
+                Method::GET,
+                "/jobs",
+                &[("host", "evil.example:8080")]
+            ),
+            StatusCode::FORBIDDEN
+        );
+        // 他のオリジンのページからのリクエストは拒否する
+        assert_eq!(
+            status(
+                &access,
+                Method::POST,
+                "/jobs/rust/pause",
+                &[("host", ADDR), ("origin", "http://evil.example")]
+            ),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status(
+                &access,
+                Method::GET,
+                "/jobs",
+                &[("host", ADDR), ("origin", "http://127.0.0.1:8080")]
+            ),
+            StatusCode::OK
+        );
+
+        // トークンは`/healthz`以外に要求する
+        let access = Access::new("0.0.0.0:8080".parse().unwrap(), Some("secret".to_string()));
+        let host = ("host", "192.0.2.1:8080");
+        assert_eq!(
+            status(&access, Method::GET, "/healthz", &[host]),
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&access, Method::GET, "/jobs", &[host]),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(
+                &access,
+                Method::GET,
+                "/jobs",
+                &[host, ("authorization", "Bearer wrong")]
+            ),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(
+                &access,
+                Method::GET,
+                "/jobs",
+                &[host, ("authorization", "Bearer secreT")]
+            ),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(
+                &access,
+                Method::GET,
+                "/jobs",
+                &[host, ("authorization", "Bearer secret")]
+            ),
+            StatusCode::OK
+        );
+    }
+}
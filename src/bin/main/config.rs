@@ -0,0 +1,641 @@
+use search_and_report::{
+    reporter::{DigestFormat, FeedFormat, ReportPolicy, RetentionPolicy, SaveFormat, SmtpSecurity},
+    retry::RetryPolicy,
+    SearchConfig,
+};
+
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+
+/// Config読み込みのエラー
+#[derive(Debug, thiserror::Error)]
+#[error("ConfigError: {0}")]
+pub struct ConfigError(pub String);
+
+/// プラットフォームの判定を行う
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub enum PlatForm {
+    YahooJp(search_and_report::platforms::YahooJp),
+}
+
+impl TryFrom<String> for PlatForm {
+    type Error = ConfigError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "YahooJp" => Ok(PlatForm::YahooJp(search_and_report::platforms::YahooJp)),
+            _ => Err(ConfigError(format!("Unexpected platform: {}.", value))),
+        }
+    }
+}
+
+impl From<PlatForm> for String {
+    fn from(value: PlatForm) -> Self {
+        match value {
+            PlatForm::YahooJp(_) => "YahooJp".to_string(),
+        }
+    }
+}
+
+/// 前回の実行が終わる前に次の実行の時刻になった場合の方針
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// 次の実行を行わない．
+    #[default]
+    Skip,
+    /// 前回の実行が終わってから実行する．
+    Queue,
+    /// 前回の実行を中断して実行する．
+    Cancel,
+}
+
+/// ファイルへの保存の設定
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FileConfig {
+    pub dir: String,
+    #[serde(default)]
+    pub format: SaveFormat,
+    /// 拡張子を除いたファイル名のテンプレート．`%Y`などで時刻を指定できる．
+    pub filename: Option<String>,
+    #[serde(default)]
+    pub gzip: bool,
+    /// 保存したファイルの保持方針
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+}
+
+/// html・Markdownのダイジェストの設定
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DigestConfig {
+    pub dir: String,
+    /// 作製する形式．空の場合はhtmlのみ．
+    #[serde(default)]
+    pub formats: Vec<DigestFormat>,
+}
+
+/// Atom・RSSのフィードの設定
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FeedConfig {
+    pub path: String,
+    #[serde(default)]
+    pub format: FeedFormat,
+    /// エントリー数の上限
+    pub max_entries: Option<usize>,
+    /// フィードのリンク先
+    pub link: Option<String>,
+}
+
+/// 外部コマンドによるリポートの設定
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CommandConfig {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// 追加の環境変数
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    pub timeout_secs: Option<u64>,
+}
+
+/// webhookによるリポートの設定
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// 送信するjsonのテンプレート
+    pub body: serde_json::Value,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub timeout_secs: Option<u64>,
+    /// 検索の失敗を通知するjsonのテンプレート
+    pub failure_body: Option<serde_json::Value>,
+}
+
+/// メールによるリポートの設定
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EmailConfig {
+    pub host: String,
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    /// 件名のテンプレート
+    pub subject: Option<String>,
+    /// 本文のテンプレート
+    pub body: Option<String>,
+}
+
+/// Configファイルの一要素．条件を複数指定した場合はORになる．
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SearchAndReportConfig {
+    #[serde(flatten)]
+    pub search_config: SearchConfig,
+    /// `false`の場合は`--job`で指定しない限り実行しない．
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub platform: PlatForm,
+    pub cron: String,
+    /// cronのタイムゾーン(例: `Asia/Tokyo`)．指定しない場合はUTC．
+    #[serde(default)]
+    pub timezone: Option<chrono_tz::Tz>,
+    /// 前回の実行と重なった場合の方針
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+    pub condition_n_per_h: Option<u32>,
+    pub condition_contain: Option<Vec<String>>,
+    pub condition_latest_in_h: Option<u32>,
+    pub report_json_dir: Option<String>,
+    /// `report_json_dir`に保存したファイルの保持方針
+    #[serde(default)]
+    pub report_json_retention: RetentionPolicy,
+    pub report_file: Option<FileConfig>,
+    pub report_digest: Option<DigestConfig>,
+    pub report_feed: Option<FeedConfig>,
+    /// OSの通知のタイトルのテンプレート
+    pub report_os_summary: Option<String>,
+    /// OSの通知の内容のテンプレート
+    pub report_os_content: Option<String>,
+    #[serde(default)]
+    pub report_os_latest: bool,
+    /// 最新ポストの通知の内容のテンプレート
+    pub report_os_latest_content: Option<String>,
+    pub report_webhook: Option<WebhookConfig>,
+    pub report_email: Option<EmailConfig>,
+    pub report_command: Option<CommandConfig>,
+    /// 検索の履歴を保存するSQLiteのファイル
+    pub report_sqlite_path: Option<String>,
+    /// リポーターごとのタイムアウト(秒)
+    pub report_timeout_secs: Option<u64>,
+    /// 複数のリポーターの実行方針
+    #[serde(default)]
+    pub report_policy: ReportPolicy,
+    /// リポーターごとの再試行の設定
+    #[serde(default)]
+    pub report_retry: RetryPolicy,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// このデフォルトはデフォルトのconfigファイルを作製する際に使われる．
+impl Default for SearchAndReportConfig {
+    fn default() -> Self {
+        SearchAndReportConfig {
+            search_config: Default::default(),
+            enabled: true,
+            platform: PlatForm::YahooJp(Default::default()),
+            cron: "0 0 6,12 * * * *".to_string(),
+            timezone: None,
+            overlap: Default::default(),
+            condition_n_per_h: Some(5),
+            condition_contain: Some(vec!["CLI".to_string()]),
+            condition_latest_in_h: Some(1),
+            report_json_dir: Some("./default_reports".to_string()),
+            report_json_retention: Default::default(),
+            report_file: None,
+            report_digest: None,
+            report_feed: None,
+            report_os_summary: None,
+            report_os_content: Some("Reported matching the condition.".to_string()),
+            report_os_latest: false,
+            report_os_latest_content: None,
+            report_webhook: None,
+            report_email: None,
+            report_command: None,
+            report_sqlite_path: None,
+            report_timeout_secs: None,
+            report_policy: Default::default(),
+            report_retry: Default::default(),
+        }
+    }
+}
+
+/// Configファイルの全体
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AllConfig {
+    /// `search_and_reports`を追加する他の設定ファイル．このファイルからの相対パス．
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// 全てのジョブで同時に実行する数の上限．指定しない場合は制限しない．
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_jobs: Option<NonZeroUsize>,
+    /// `--listen`のAPIに要求するBearerトークン．指定しない場合は認証を行わない．
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_token: Option<String>,
+    #[serde(default)]
+    pub search_and_reports: Vec<SearchAndReportConfig>,
+}
+
+impl Default for AllConfig {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            max_concurrent_jobs: None,
+            api_token: None,
+            search_and_reports: vec![Default::default()],
+        }
+    }
+}
+
+/// 設定ファイルの形式．拡張子から判定する．
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    pub fn from_path(path: &Path) -> Result<Self, ConfigError> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Ok(ConfigFormat::Json),
+            Some("toml") => Ok(ConfigFormat::Toml),
+            Some("yaml" | "yml") => Ok(ConfigFormat::Yaml),
+            _ => Err(ConfigError(format!(
+                "{:?}: Unexpected extension. Use json, toml or yaml.",
+                path
+            ))),
+        }
+    }
+
+    /// jsonの値として読み込む．
+    pub fn parse(&self, source: &str) -> Result<Value, ConfigError> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(source).map_err(|e| e.to_string()),
+            ConfigFormat::Toml => toml::from_str(source).map_err(|e| e.to_string()),
+            ConfigFormat::Yaml => serde_yaml::from_str(source).map_err(|e| e.to_string()),
+        }
+        .map_err(ConfigError)
+    }
+
+    pub fn serialize(&self, config: &AllConfig) -> Result<String, ConfigError> {
+        match self {
+            ConfigFormat::Json => serde_json::to_string_pretty(config).map_err(|e| e.to_string()),
+            ConfigFormat::Toml => toml::to_string_pretty(config).map_err(|e| e.to_string()),
+            // タグを用いずに列挙型を出力するため，jsonの値を経由する
+            ConfigFormat::Yaml => serde_json::to_value(config)
+                .map_err(|e| e.to_string())
+                .and_then(|value| serde_yaml::to_string(&value).map_err(|e| e.to_string())),
+        }
+        .map_err(ConfigError)
+    }
+}
+
+/// ジョブの名前が空でなく，重複していないことを確認する．
+pub fn check_names(config: &AllConfig) -> Result<(), ConfigError> {
+    let mut names = BTreeSet::new();
+    for job in config.search_and_reports.iter() {
+        let name = &job.search_config.name;
+        if name.trim().is_empty() {
+            return Err(ConfigError("Job name must not be empty.".to_string()));
+        }
+        if !names.insert(name.as_str()) {
+            return Err(ConfigError(format!("Duplicate job name: {}.", name)));
+        }
+    }
+    Ok(())
+}
+
+/// 環境変数の参照
+static ENV_PAT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").expect("pattern is valid"));
+
+/// 文字列の値の`${ENV_VAR}`を環境変数で置き換える．設定されていない環境変数をパスとともに返す．
+pub fn interpolate_env(value: &mut Value, path: &str) -> Vec<(String, String)> {
+    let mut missing = Vec::new();
+    match value {
+        Value::String(string) => {
+            let interpolated = ENV_PAT.replace_all(string, |captures: &Captures| {
+                std::env::var(&captures[1]).unwrap_or_else(|_| {
+                    missing.push((path.to_string(), captures[1].to_string()));
+                    String::new()
+                })
+            });
+            *string = interpolated.into_owned();
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter_mut().enumerate() {
+                missing.extend(interpolate_env(value, &format!("{}[{}]", path, i)));
+            }
+        }
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                missing.extend(interpolate_env(value, &child));
+            }
+        }
+        _ => {}
+    }
+    missing
+}
+
+/// `include`のパスを取り出す．
+pub fn includes(root: &Value, path: &Path) -> Result<Vec<PathBuf>, ConfigError> {
+    let base = path.parent().unwrap_or(Path::new("."));
+    match root.get("include") {
+        None => Ok(Vec::new()),
+        Some(include) => include
+            .as_array()
+            .and_then(|include| {
+                include
+                    .iter()
+                    .map(|path| path.as_str().map(|path| base.join(path)))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or_else(|| ConfigError("include must be a list of paths.".to_string())),
+    }
+}
+
+/// 設定ファイルを読み込み，環境変数の置き換えと`include`したファイルの結合を行う．
+/// 複数のファイルから`include`されたファイルは一度だけ結合し，循環した`include`はエラーとする．
+/// 読み込んだ全てのファイルのパスも返す．
+pub fn load_value(path: &Path) -> Result<(Value, Vec<PathBuf>), ConfigError> {
+    /// `stack`は`include`している途中のファイル．既に読み込んだファイルの場合はNoneを返す．
+    fn load(
+        path: &Path,
+        stack: &mut Vec<PathBuf>,
+        files: &mut Vec<PathBuf>,
+    ) -> Result<Option<Value>, ConfigError> {
+        let with_path = |e: ConfigError| ConfigError(format!("{:?}: {}", path, e.0));
+
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| with_path(ConfigError(e.to_string())))?;
+        if stack.contains(&canonical) {
+            return Err(with_path(ConfigError("Circular include.".to_string())));
+        }
+        if files.contains(&canonical) {
+            return Ok(None);
+        }
+        files.push(canonical.clone());
+        stack.push(canonical);
+
+        let source =
+            std::fs::read_to_string(path).map_err(|e| with_path(ConfigError(e.to_string())))?;
+        let mut root = ConfigFormat::from_path(path)?
+            .parse(&source)
+            .map_err(with_path)?;
+        if let Some((field, name)) = interpolate_env(&mut root, "").into_iter().next() {
+            return Err(with_path(ConfigError(format!(
+                "{}: Environment variable {} is not set.",
+                field, name
+            ))));
+        }
+
+        for include in includes(&root, path).map_err(with_path)? {
+            let Some(included) = load(&include, stack, files)? else {
+                continue;
+            };
+            let jobs = included
+                .get("search_and_reports")
+                .and_then(|jobs| jobs.as_array())
+                .cloned()
+                .unwrap_or_default();
+            if let Some(Value::Array(root_jobs)) = root.get_mut("search_and_reports") {
+                root_jobs.extend(jobs);
+            } else if let Some(root) = root.as_object_mut() {
+                root.insert("search_and_reports".to_string(), Value::Array(jobs));
+            }
+        }
+        if let Some(root) = root.as_object_mut() {
+            root.remove("include");
+        }
+        stack.pop();
+        Ok(Some(root))
+    }
+
+    let mut files = Vec::new();
+    let root = load(path, &mut Vec::new(), &mut files)?.unwrap_or_default();
+    Ok((root, files))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        load_value, AllConfig, CommandConfig, DigestConfig, FeedConfig, FileConfig, OverlapPolicy,
+        PlatForm, SearchAndReportConfig, SearchConfig, WebhookConfig,
+    };
+    use search_and_report::{
+        reporter::{DigestFormat, FeedFormat, ReportPolicy, RetentionPolicy, SaveFormat},
+        retry::RetryPolicy,
+        ErrorBudget,
+    };
+    use std::num::NonZeroUsize;
+
+    #[tracing_test::traced_test]
+    #[test]
+    fn test_deserialize() {
+        let config_json = r#"
+{
+    "max_concurrent_jobs": 2,
+    "api_token": "secret",
+    "search_and_reports": [
+        {
+            "name": "rust",
+            "keywords": ["Rust"],
+            "parse_error_budget": {"max_count": 3},
+            "request_retry": {"max_retries": 0, "initial_delay_ms": 0, "max_delay_ms": 0},
+            "platform": "YahooJp",
+            "cron": "0 0 6 * * * *",
+            "timezone": "Asia/Tokyo",
+            "overlap": "queue",
+            "condition_n_per_h": 10,
+            "condition_contain": ["CLI", "TUI"],
+            "report_json_dir": "./my_reports",
+            "report_json_retention": {"max_age_days": 30, "daily_rollup": true},
+            "report_file": {"dir": "./my_reports", "format": "jsonl", "gzip": true},
+            "report_digest": {"dir": "./my_digests", "formats": ["html", "markdown"]},
+            "report_feed": {"path": "./feeds/rust.xml", "format": "rss", "max_entries": 20},
+            "report_os_summary": "{{ count }} posts",
+            "report_webhook": {
+                "url": "http://localhost:8080/hook",
+                "body": {"text": "{{ summary }}"},
+                "headers": {"Authorization": "Bearer token"},
+                "failure_body": {"text": "{{ job_name }}: {{ error }}"}
+            },
+            "report_command": {"program": "./notify.sh", "args": ["--quiet"]},
+            "report_sqlite_path": "./history.sqlite3",
+            "report_timeout_secs": 30,
+            "report_policy": "fail_fast",
+            "report_retry": {"max_retries": 5}
+        }
+    ] 
+}
+            "#;
+
+        let mut deserializer = serde_json::Deserializer::from_str(config_json);
+
+        let res: Result<AllConfig, _> = serde_path_to_error::deserialize(&mut deserializer);
+
+        let config = AllConfig {
+            include: Vec::new(),
+            max_concurrent_jobs: NonZeroUsize::new(2),
+            api_token: Some("secret".to_string()),
+            search_and_reports: vec![SearchAndReportConfig {
+                search_config: SearchConfig {
+                    name: "rust".to_string(),
+                    keywords: vec!["Rust".to_string()],
+                    parse_error_budget: ErrorBudget::MaxCount(3),
+                    request_retry: RetryPolicy::NONE,
+                },
+                enabled: true,
+                platform: PlatForm::YahooJp(Default::default()),
+                cron: "0 0 6 * * * *".to_string(),
+                timezone: Some(chrono_tz::Asia::Tokyo),
+                overlap: OverlapPolicy::Queue,
+                condition_n_per_h: Some(10),
+                condition_contain: Some(vec!["CLI".to_string(), "TUI".to_string()]),
+                condition_latest_in_h: None,
+                report_json_dir: Some("./my_reports".to_string()),
+                report_json_retention: RetentionPolicy {
+                    max_age_days: Some(30),
+                    daily_rollup: true,
+                    ..Default::default()
+                },
+                report_file: Some(FileConfig {
+                    dir: "./my_reports".to_string(),
+                    format: SaveFormat::Jsonl,
+                    filename: None,
+                    gzip: true,
+                    retention: Default::default(),
+                }),
+                report_digest: Some(DigestConfig {
+                    dir: "./my_digests".to_string(),
+                    formats: vec![DigestFormat::Html, DigestFormat::Markdown],
+                }),
+                report_feed: Some(FeedConfig {
+                    path: "./feeds/rust.xml".to_string(),
+                    format: FeedFormat::Rss,
+                    max_entries: Some(20),
+                    link: None,
+                }),
+                report_os_summary: Some("{{ count }} posts".to_string()),
+                report_os_content: None,
+                report_os_latest: false,
+                report_os_latest_content: None,
+                report_webhook: Some(WebhookConfig {
+                    url: "http://localhost:8080/hook".to_string(),
+                    body: serde_json::json!({"text": "{{ summary }}"}),
+                    headers: [("Authorization".to_string(), "Bearer token".to_string())]
+                        .into_iter()
+                        .collect(),
+                    timeout_secs: None,
+                    failure_body: Some(serde_json::json!({"text": "{{ job_name }}: {{ error }}"})),
+                }),
+                report_email: None,
+                report_command: Some(CommandConfig {
+                    program: "./notify.sh".to_string(),
+                    args: vec!["--quiet".to_string()],
+                    env: Default::default(),
+                    timeout_secs: None,
+                }),
+                report_sqlite_path: Some("./history.sqlite3".to_string()),
+                report_timeout_secs: Some(30),
+                report_policy: ReportPolicy::FailFast,
+                report_retry: RetryPolicy {
+                    max_retries: 5,
+                    ..Default::default()
+                },
+            }],
+        };
+
+        assert_eq!(res.unwrap(), config);
+    }
+
+    #[test]
+    fn test_load_value() {
+        let dir_path =
+            std::env::temp_dir().join(format!("search_and_report_load_{}", std::process::id()));
+        std::fs::create_dir_all(dir_path.join("jobs")).unwrap();
+        std::env::set_var("SEARCH_AND_REPORT_TEST_DIR", "./env_reports");
+
+        let config_toml = r#"
+include = ["jobs/go.yaml"]
+
+[[search_and_reports]]
+name = "rust"
+keywords = ["Rust"]
+platform = "YahooJp"
+cron = "0 0 6 * * * *"
+report_json_dir = "${SEARCH_AND_REPORT_TEST_DIR}/rust"
+"#;
+        let go_yaml = r#"
+search_and_reports:
+  - name: go
+    keywords: ["Go"]
+    platform: YahooJp
+    cron: "0 0 12 * * * *"
+"#;
+        std::fs::write(dir_path.join("config.toml"), config_toml).unwrap();
+        std::fs::write(dir_path.join("jobs/go.yaml"), go_yaml).unwrap();
+
+        let (root, files) = load_value(&dir_path.join("config.toml")).unwrap();
+        let config: AllConfig = serde_json::from_value(root).unwrap();
+        assert!(config.include.is_empty());
+        assert_eq!(
+            config
+                .search_and_reports
+                .iter()
+                .map(|job| job.search_config.keywords.join(" "))
+                .collect::<Vec<_>>(),
+            vec!["Rust", "Go"]
+        );
+        assert_eq!(
+            config.search_and_reports[0].report_json_dir.as_deref(),
+            Some("./env_reports/rust")
+        );
+        assert_eq!(files.len(), 2);
+
+        // 複数のファイルから`include`されたファイルは一度だけ結合する
+        std::fs::write(
+            dir_path.join("jobs/common.yaml"),
+            "search_and_reports: [{name: common}]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir_path.join("jobs/go.yaml"),
+            "include: [\"common.yaml\"]\nsearch_and_reports: []\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir_path.join("config.toml"),
+            config_toml.replace(
+                r#"include = ["jobs/go.yaml"]"#,
+                r#"include = ["jobs/go.yaml", "jobs/common.yaml"]"#,
+            ),
+        )
+        .unwrap();
+        let (root, files) = load_value(&dir_path.join("config.toml")).unwrap();
+        assert_eq!(root["search_and_reports"].as_array().unwrap().len(), 2);
+        assert_eq!(files.len(), 3);
+
+        // 設定されていない環境変数と循環した`include`はエラー
+        std::fs::write(
+            dir_path.join("jobs/go.yaml"),
+            "include: [\"../config.toml\"]\nsearch_and_reports: []\n",
+        )
+        .unwrap();
+        assert!(load_value(&dir_path.join("config.toml")).is_err());
+        std::fs::write(
+            dir_path.join("jobs/go.yaml"),
+            "search_and_reports:\n  - report_json_dir: ${SEARCH_AND_REPORT_TEST_UNSET}\n",
+        )
+        .unwrap();
+        assert!(load_value(&dir_path.join("config.toml")).is_err());
+
+        std::fs::remove_dir_all(&dir_path).unwrap();
+    }
+}
@@ -0,0 +1,1708 @@
+/// 設定ファイルについて
+mod config;
+
+/// 設定ファイルの検証について
+mod validate;
+
+/// デーモンの状態の確認・操作を行うHTTPのAPIについて
+mod api;
+
+use config::{
+    AllConfig, ConfigError, ConfigFormat, OverlapPolicy, PlatForm, SearchAndReportConfig,
+};
+use search_and_report::{
+    clock::{Clock, SystemClock},
+    platforms::YahooJp,
+    predicates::{self, PredListAny},
+    reporter::{self, ReportTemplate, ReporterList},
+    schedule::CronSchedule,
+    Report, SearchOutcome,
+};
+
+use chrono::{DateTime, Utc};
+use std::process::ExitCode;
+use std::sync::Arc;
+use tokio_cron_scheduler::{Job, JobScheduler, JobSchedulerError};
+use tracing::{info, Instrument};
+
+/// ジョブの最後の実行の結果
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum RunResult {
+    Ok {
+        posts: usize,
+        /// 一致した条件の名前
+        matched: Vec<String>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// ジョブの実行状況
+#[derive(Debug, Clone, Default)]
+struct JobStatus {
+    last_run: Option<DateTime<Utc>>,
+    last_result: Option<RunResult>,
+    /// 最後に成功した実行で取得したポスト
+    last_posts: search_and_report::Posts,
+    next_run: Option<DateTime<Utc>>,
+}
+
+/// 全てのジョブで共有する同時実行数の上限．Noneの場合は制限しない．
+type ConcurrencyLimit = Option<Arc<tokio::sync::Semaphore>>;
+
+/// 設定から同時実行数の上限を作製する．
+fn concurrency_limit(config: &AllConfig) -> ConcurrencyLimit {
+    config
+        .max_concurrent_jobs
+        .map(|max| Arc::new(tokio::sync::Semaphore::new(max.get())))
+}
+
+/// スケジューラーに登録した次回の実行のidと停止の状態．次回の実行ごとにidが変わる．
+#[derive(Clone)]
+struct JobHandle {
+    job_id: Arc<std::sync::Mutex<Option<uuid::Uuid>>>,
+    stopped: Arc<std::sync::atomic::AtomicBool>,
+    /// 実行中の検索・リポートの数
+    running: Arc<tokio::sync::watch::Sender<usize>>,
+    overlap: OverlapPolicy,
+    /// 同じジョブの実行が重ならないように実行中に保持するロック
+    exclusive: Arc<tokio::sync::Mutex<()>>,
+    /// 実行中の検索・リポートを中断するハンドル
+    abort: Arc<std::sync::Mutex<Option<futures::future::AbortHandle>>>,
+    limit: ConcurrencyLimit,
+    /// 一時停止中はスケジュールによる実行を行わない
+    paused: Arc<std::sync::atomic::AtomicBool>,
+    status: Arc<std::sync::Mutex<JobStatus>>,
+}
+
+/// 実行中であることを表す．破棄すると実行中の数を減らす．
+struct RunningGuard(Arc<tokio::sync::watch::Sender<usize>>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.send_modify(|running| *running -= 1);
+    }
+}
+
+impl JobHandle {
+    fn new(overlap: OverlapPolicy, limit: ConcurrencyLimit) -> Self {
+        Self {
+            job_id: Default::default(),
+            stopped: Default::default(),
+            running: Arc::new(tokio::sync::watch::channel(0).0),
+            overlap,
+            exclusive: Default::default(),
+            abort: Default::default(),
+            limit,
+            paused: Default::default(),
+            status: Default::default(),
+        }
+    }
+
+    /// 再読み込みで置き換えた同じ名前のジョブの実行と重ならないよう，ロックと中断のハンドルを引き継ぐ．
+    /// 一時停止と実行の結果も共有し，置き換えの時点で実行中だった結果も記録する．
+    fn inherit(mut self, previous: &JobHandle) -> Self {
+        self.exclusive = Arc::clone(&previous.exclusive);
+        self.abort = Arc::clone(&previous.abort);
+        self.paused = Arc::clone(&previous.paused);
+        self.status = Arc::clone(&previous.status);
+        self
+    }
+
+    /// 次回の実行を登録する．
+    async fn add(&self, scheduler: &JobScheduler, job: Job) -> Result<(), JobSchedulerError> {
+        let job_id = job.guid();
+        *self.job_id.lock().unwrap() = Some(job_id);
+        scheduler.add(job).await?;
+        // 登録の間に停止した場合は取り消す
+        if self.is_stopped() {
+            scheduler.remove(&job_id).await?;
+        }
+        Ok(())
+    }
+
+    /// 次回以降の実行を取り消す．実行中の検索・リポートはそのまま完了させる．
+    async fn stop(&self, scheduler: &JobScheduler) -> Result<(), JobSchedulerError> {
+        self.stopped
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        let job_id = self.job_id.lock().unwrap().take();
+        if let Some(job_id) = job_id {
+            scheduler.remove(&job_id).await?;
+        }
+        Ok(())
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// 実行の開始を記録する．返り値を破棄するまで実行中とみなす．
+    fn begin_run(&self) -> RunningGuard {
+        self.running.send_modify(|running| *running += 1);
+        RunningGuard(Arc::clone(&self.running))
+    }
+
+    fn is_running(&self) -> bool {
+        *self.running.borrow() > 0
+    }
+
+    /// 実行中の検索・リポートが全て完了するまで待つ．
+    async fn wait_idle(&self) {
+        let mut receiver = self.running.subscribe();
+        // 送信側はこのハンドルが保持しているため閉じることはない
+        let _ = receiver.wait_for(|running| *running == 0).await;
+    }
+
+    /// 前回の実行と重なった場合の方針と，全体の同時実行数の上限に従って実行する．
+    async fn run<F: std::future::Future<Output = ()>>(&self, future: F) {
+        // 停止を確かめるより前に実行中とすることで，停止の後に完了を待つ側が取りこぼさない
+        let _running = self.begin_run();
+        let _exclusive = match self.overlap {
+            OverlapPolicy::Skip => match self.exclusive.try_lock() {
+                Ok(exclusive) => exclusive,
+                Err(_) => {
+                    tracing::warn!("Skipped. The previous run is still in progress.");
+                    return;
+                }
+            },
+            OverlapPolicy::Queue => self.exclusive.lock().await,
+            OverlapPolicy::Cancel => {
+                if let Some(abort) = self.abort.lock().unwrap().take() {
+                    abort.abort();
+                }
+                self.exclusive.lock().await
+            }
+        };
+        // 待っている間に停止した場合は実行しない
+        if self.is_stopped() {
+            return;
+        }
+
+        // 上限を待っている間も中断できるようにする
+        let limit = self.limit.clone();
+        let (future, abort) = futures::future::abortable(async move {
+            let _permit = match limit.as_ref() {
+                Some(limit) => Some(limit.acquire().await.expect("semaphore is never closed")),
+                None => None,
+            };
+            future.await
+        });
+        *self.abort.lock().unwrap() = Some(abort);
+        if future.await.is_err() {
+            tracing::warn!("Cancelled by the next run.");
+        }
+    }
+
+    /// 検索・リポートを実行し，結果を記録する．
+    async fn run_task(&self, task: &JobTask) {
+        self.run(async {
+            let started = task.clock.now();
+            let res = task.run().await;
+            search_and_report::metrics::global().record_run(
+                &task.search_config.name,
+                res.is_ok(),
+                task.clock.now(),
+            );
+
+            let mut status = self.status.lock().unwrap();
+            status.last_run = Some(started);
+            match res {
+                Ok(outcome) => {
+                    status.last_result = Some(RunResult::Ok {
+                        posts: outcome.posts.len(),
+                        matched: outcome
+                            .verdicts
+                            .iter()
+                            .filter(|verdict| verdict.matched)
+                            .map(|verdict| verdict.name.clone())
+                            .collect(),
+                    });
+                    status.last_posts = outcome.posts;
+                }
+                Err(e) => {
+                    tracing::error!("Error occurred. {:?}", e);
+                    status.last_result = Some(RunResult::Error {
+                        message: e.to_string(),
+                    });
+                }
+            }
+        })
+        .await
+    }
+
+    fn status(&self) -> JobStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// スケジュールによる実行を一時停止する．
+    fn pause(&self) {
+        self.paused.store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    fn resume(&self) {
+        self.paused
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused.load(std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+/// 実行を待つ間に壁時計を確かめ直す間隔の上限．単調時計はスリープ中に進まないため，
+/// 復帰後もこの間隔のうちに実行の日時を過ぎたことに気付く．
+const WAKE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// スケジュールの`after`より後の次回の実行を一回限りのジョブとして作製する．実行時に更に次回を登録する．
+fn next_job(
+    schedule: Arc<CronSchedule>,
+    clock: Arc<dyn Clock>,
+    task: Arc<JobTask>,
+    handle: JobHandle,
+    after: DateTime<Utc>,
+) -> Result<Option<Job>, JobSchedulerError> {
+    let next = schedule.next_after(after);
+    handle.status.lock().unwrap().next_run = next;
+    let Some(next) = next else {
+        return Ok(None);
+    };
+    info!("Next run at {}.", next.with_timezone(&schedule.timezone()));
+    wake_job(schedule, clock, task, handle, next).map(Some)
+}
+
+/// `next`の実行を待つ一回限りのジョブを作製する．起きた時点で壁時計が`next`に達していなければ再び待つ．
+fn wake_job(
+    schedule: Arc<CronSchedule>,
+    clock: Arc<dyn Clock>,
+    task: Arc<JobTask>,
+    handle: JobHandle,
+    next: DateTime<Utc>,
+) -> Result<Job, JobSchedulerError> {
+    let wait = (next - clock.now())
+        .to_std()
+        .unwrap_or_default()
+        .min(WAKE_INTERVAL);
+    let instant = std::time::Instant::now() + wait;
+    // 実行時にも登録時のジョブのspanでログを記録する
+    let span = tracing::Span::current();
+
+    Job::new_one_shot_at_instant_async(instant, move |_id, scheduler| {
+        let schedule = Arc::clone(&schedule);
+        let clock = Arc::clone(&clock);
+        let task = Arc::clone(&task);
+        let handle = handle.clone();
+        let span = span.clone();
+
+        Box::pin(
+            async move {
+                let _running = handle.begin_run();
+                if handle.is_stopped() {
+                    return;
+                }
+                let now = clock.now();
+                let due = now >= next;
+                // 次回は実際に起きた日時より後とする．壁時計が戻っても同じ日時に二回実行しない
+                let job = if due {
+                    next_job(schedule, clock, Arc::clone(&task), handle.clone(), now)
+                } else {
+                    wake_job(schedule, clock, Arc::clone(&task), handle.clone(), next).map(Some)
+                };
+                match job {
+                    Ok(Some(job)) => {
+                        if let Err(e) = handle.add(&scheduler, job).await {
+                            tracing::error!("Failed to schedule next run. {:?}", e);
+                        }
+                    }
+                    Ok(None) => info!("No more runs are scheduled."),
+                    Err(e) => tracing::error!("Failed to schedule next run. {:?}", e),
+                }
+                if !due {
+                    return;
+                }
+                if handle.is_paused() {
+                    info!("Paused. Skipped this run.");
+                } else {
+                    handle.run_task(&task).await;
+                }
+            }
+            .instrument(span),
+        )
+    })
+}
+
+/// 設定から作製した，スケジューラーに登録する前のジョブ
+struct PreparedJob {
+    config: SearchAndReportConfig,
+    schedule: Arc<CronSchedule>,
+    task: Arc<JobTask>,
+}
+
+/// 設定から作製した検索・リポートの内容
+struct JobTask {
+    search_config: search_and_report::SearchConfig,
+    platform: PlatForm,
+    report_list: ReporterList,
+    pred_list: PredListAny,
+    clock: Arc<dyn Clock>,
+}
+
+impl JobTask {
+    /// ログに用いるジョブのspan
+    fn span(&self) -> tracing::Span {
+        tracing::info_span!("job", name = %self.search_config.name)
+    }
+
+    /// 検索し，条件に一致した場合はリポートする．
+    async fn run(&self) -> Result<SearchOutcome, search_and_report::error::Error> {
+        // プラットフォームごとにマッチング
+        match &self.platform {
+            PlatForm::YahooJp(platform) => {
+                search_and_report::search_and_report(
+                    &self.search_config,
+                    platform,
+                    &self.report_list,
+                    |posts| self.pred_list.verdicts(posts),
+                    self.clock.as_ref(),
+                )
+                .await
+            }
+        }
+    }
+
+    /// 検索して条件を判定する．リポート・記録は行わない．
+    async fn dry_run(&self) -> Result<SearchOutcome, search_and_report::error::Error> {
+        let output = match &self.platform {
+            PlatForm::YahooJp(_) => {
+                search_and_report::search::<YahooJp>(&self.search_config, self.clock.as_ref())
+                    .await?
+            }
+        };
+        let verdicts = self.pred_list.verdicts(&output.posts);
+
+        Ok(SearchOutcome {
+            posts: output.posts,
+            verdicts,
+        })
+    }
+}
+
+/// スケジューラーに登録したジョブ
+#[derive(Clone)]
+struct ScheduledJob {
+    config: SearchAndReportConfig,
+    task: Arc<JobTask>,
+    handle: JobHandle,
+}
+
+impl ScheduledJob {
+    /// スケジュールとは別に直ちに実行する．一時停止中でも実行する．
+    fn trigger(&self) {
+        let task = Arc::clone(&self.task);
+        let handle = self.handle.clone();
+        let span = task.span();
+        tokio::spawn(async move { handle.run_task(&task).await }.instrument(span));
+    }
+}
+
+/// 設定から条件とリポーターを作製する．設定が不正な場合はエラーとなる．
+fn prepare_job(
+    search_and_report_config: SearchAndReportConfig,
+    clock: Arc<dyn Clock>,
+) -> Result<PreparedJob, Box<dyn std::error::Error>> {
+    let config = search_and_report_config.clone();
+    let SearchAndReportConfig {
+        search_config,
+        enabled: _,
+        platform,
+        cron,
+        timezone,
+        overlap: _,
+        condition_n_per_h,
+        condition_contain,
+        condition_latest_in_h,
+        report_json_dir,
+        report_json_retention,
+        report_file,
+        report_digest,
+        report_feed,
+        report_os_summary,
+        report_os_content,
+        report_os_latest,
+        report_os_latest_content,
+        report_webhook,
+        report_email,
+        report_command,
+        report_sqlite_path,
+        report_timeout_secs,
+        report_policy,
+        report_retry,
+    } = search_and_report_config;
+
+    let schedule = CronSchedule::new(&cron, timezone.unwrap_or(chrono_tz::UTC))?;
+
+    // Conditionについて
+    let mut pred_list = PredListAny::new();
+    condition_n_per_h.into_iter().for_each(|condition_n_per_h| {
+        let pred = predicates::NumberPerDuration::new(
+            condition_n_per_h as usize,
+            chrono::Duration::hours(1),
+        );
+        pred_list.append_pred("condition_n_per_h", move |posts| pred.predicate(posts));
+    });
+    condition_contain.into_iter().for_each(|condition_contain| {
+        let pred = predicates::ContainsKeyWords::new(condition_contain);
+        pred_list.append_pred("condition_contain", move |posts| pred.predicate(posts));
+    });
+    condition_latest_in_h
+        .into_iter()
+        .for_each(|condition_latest_in_h| {
+            let pred = predicates::LatestPostTime::new(
+                chrono::Duration::hours(condition_latest_in_h as i64),
+                Arc::clone(&clock),
+            );
+            pred_list.append_pred("condition_latest_in_h", move |posts| pred.predicate(posts));
+        });
+
+    // Reportについて
+    let mut report_list = ReporterList::new()
+        .with_job_name(search_config.name.as_str())
+        .with_policy(report_policy)
+        .with_retry(report_retry)
+        .with_clock(Arc::clone(&clock));
+    if let Some(report_timeout_secs) = report_timeout_secs {
+        report_list =
+            report_list.with_timeout(Some(std::time::Duration::from_secs(report_timeout_secs)));
+    }
+    report_json_dir.into_iter().for_each(|report_json_dir| {
+        let report = reporter::JsonSaveReporter::new(
+            std::path::Path::new(&report_json_dir),
+            Arc::clone(&clock),
+        )
+        .with_retention(report_json_retention);
+        report_list.append_reporter(report);
+    });
+    if let Some(report_file) = report_file {
+        let mut report = reporter::FileSaveReporter::new(report_file.dir, Arc::clone(&clock))
+            .with_format(report_file.format)
+            .with_gzip(report_file.gzip)
+            .with_retention(report_file.retention);
+        if let Some(filename) = report_file.filename {
+            report = report.with_filename_template(ReportTemplate::new_filename(filename)?);
+        }
+        report_list.append_reporter(report);
+    }
+    if let Some(report_digest) = report_digest {
+        let mut report = reporter::DigestReporter::new(report_digest.dir, Arc::clone(&clock));
+        if !report_digest.formats.is_empty() {
+            report = report.with_formats(&report_digest.formats);
+        }
+        report_list.append_reporter(report);
+    }
+    if let Some(report_feed) = report_feed {
+        let mut report = reporter::FeedReporter::new(report_feed.path, Arc::clone(&clock))
+            .with_format(report_feed.format);
+        if let Some(max_entries) = report_feed.max_entries {
+            report = report.with_max_entries(max_entries);
+        }
+        if let Some(link) = report_feed.link {
+            report = report.with_link(link);
+        }
+        report_list.append_reporter(report);
+    }
+    let report_os_summary = report_os_summary.map(ReportTemplate::new).transpose()?;
+    if let Some(report_os_content) = report_os_content {
+        let mut report =
+            reporter::StaticNotificationReporter::new(ReportTemplate::new(report_os_content)?);
+        if let Some(summary) = report_os_summary.clone() {
+            report = report.with_summary(summary);
+        }
+        report_list.append_reporter(report);
+    }
+    if report_os_latest {
+        let mut report = reporter::LatestPostNotificationReporter::default();
+        if let Some(summary) = report_os_summary {
+            report = report.with_summary(summary);
+        }
+        if let Some(content) = report_os_latest_content {
+            report = report.with_content(ReportTemplate::new(content)?);
+        }
+        report_list.append_reporter(report);
+    }
+    if let Some(report_webhook) = report_webhook {
+        let mut report = reporter::WebhookReporter::new(report_webhook.url, report_webhook.body)?
+            .with_headers(&report_webhook.headers)?;
+        if let Some(timeout_secs) = report_webhook.timeout_secs {
+            report = report.with_timeout(std::time::Duration::from_secs(timeout_secs));
+        }
+        if let Some(failure_body) = report_webhook.failure_body {
+            report = report.with_failure_body(failure_body)?;
+        }
+        report_list.append_reporter(report);
+    }
+    if let Some(report_email) = report_email {
+        let mut report =
+            reporter::EmailReporter::new(report_email.host, &report_email.from, &report_email.to)?
+                .with_security(report_email.security);
+        if let Some(port) = report_email.port {
+            report = report.with_port(port);
+        }
+        if let (Some(username), Some(password)) = (report_email.username, report_email.password) {
+            report = report.with_credentials(username, password);
+        }
+        if let Some(subject) = report_email.subject {
+            report = report.with_subject_template(ReportTemplate::new(subject)?);
+        }
+        if let Some(body) = report_email.body {
+            report = report.with_body_template(ReportTemplate::new(body)?);
+        }
+        report_list.append_reporter(report);
+    }
+    if let Some(report_command) = report_command {
+        let mut report = reporter::CommandReporter::new(report_command.program)
+            .with_args(&report_command.args)
+            .with_env(&report_command.env);
+        if let Some(timeout_secs) = report_command.timeout_secs {
+            report = report.with_timeout(std::time::Duration::from_secs(timeout_secs));
+        }
+        report_list.append_reporter(report);
+    }
+    if let Some(report_sqlite_path) = report_sqlite_path {
+        let report = reporter::SqliteReporter::new(report_sqlite_path, Arc::clone(&clock))?;
+        report_list.append_reporter(report);
+    }
+
+    let task = Arc::new(JobTask {
+        search_config,
+        platform,
+        report_list,
+        pred_list,
+        clock,
+    });
+
+    Ok(PreparedJob {
+        config,
+        schedule: Arc::new(schedule),
+        task,
+    })
+}
+
+impl PreparedJob {
+    /// スケジューラーに登録する．`previous`は再読み込みで置き換えた同じ名前のジョブ．
+    async fn start(
+        self,
+        scheduler: &JobScheduler,
+        limit: ConcurrencyLimit,
+        clock: Arc<dyn Clock>,
+        previous: Option<&JobHandle>,
+    ) -> Result<ScheduledJob, JobSchedulerError> {
+        let span = self.task.span();
+        async move {
+            let mut handle = JobHandle::new(self.config.overlap, limit);
+            if let Some(previous) = previous {
+                handle = handle.inherit(previous);
+            }
+
+            // スケジュール
+            let now = clock.now();
+            if let Some(job) = next_job(
+                self.schedule,
+                clock,
+                Arc::clone(&self.task),
+                handle.clone(),
+                now,
+            )? {
+                handle.add(scheduler, job).await?;
+            }
+
+            Ok(ScheduledJob {
+                config: self.config,
+                task: self.task,
+                handle,
+            })
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+/// 実行中のジョブと新しい設定を比較し，変更のあったジョブのみを削除・追加する．
+/// 削除したジョブは実行中の検索・リポートが完了するまで`retired`に残す．
+/// 新しい設定が不正な場合は実行中のジョブを変更せずにエラーを返す．
+async fn reload_jobs(
+    scheduler: &JobScheduler,
+    jobs: &mut Vec<ScheduledJob>,
+    retired: &mut Vec<ScheduledJob>,
+    config: AllConfig,
+    limit: &ConcurrencyLimit,
+    clock: Arc<dyn Clock>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let AllConfig {
+        search_and_reports, ..
+    } = config;
+
+    // 設定が同じジョブはそのまま残す
+    let mut unchanged = vec![false; jobs.len()];
+    let mut added = Vec::new();
+    for search_and_report_config in search_and_reports.into_iter() {
+        let same = jobs
+            .iter()
+            .enumerate()
+            .position(|(i, job)| !unchanged[i] && job.config == search_and_report_config);
+        match same {
+            Some(i) => unchanged[i] = true,
+            None => added.push(search_and_report_config),
+        }
+    }
+
+    // 全て作製できた場合のみスケジューラーを変更する
+    let added = added
+        .into_iter()
+        .map(|config| prepare_job(config, Arc::clone(&clock)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut unchanged = unchanged.into_iter();
+    let (kept, removed): (Vec<_>, Vec<_>) = std::mem::take(jobs)
+        .into_iter()
+        .partition(|_| unchanged.next().unwrap_or(false));
+    *jobs = kept;
+
+    for job in removed.iter() {
+        info!("Removing job: {}", job.config.search_config.name);
+        job.handle.stop(scheduler).await?;
+    }
+    for job in added.into_iter() {
+        info!("Adding job: {}", job.config.search_config.name);
+        // 同じ名前のジョブを置き換える場合は状態を引き継ぐ
+        let previous = removed
+            .iter()
+            .find(|removed| removed.config.search_config.name == job.config.search_config.name)
+            .map(|removed| &removed.handle);
+        jobs.push(
+            job.start(scheduler, limit.clone(), Arc::clone(&clock), previous)
+                .await?,
+        );
+    }
+    retired.extend(removed);
+    flush_retired(retired).await;
+
+    Ok(())
+}
+
+/// ジョブのリポーターに溜めたリポートを送る．
+async fn flush_job(job: &ScheduledJob) {
+    if let Err(e) = job.task.report_list.flush().await {
+        tracing::error!(
+            "Failed to flush job {}. {}",
+            job.config.search_config.name,
+            e
+        );
+    }
+}
+
+/// 削除したジョブのうち実行中の検索・リポートが完了したものをフラッシュして破棄する．
+async fn flush_retired(retired: &mut Vec<ScheduledJob>) {
+    let (running, idle): (Vec<_>, Vec<_>) = std::mem::take(retired)
+        .into_iter()
+        .partition(|job| job.handle.is_running());
+    *retired = running;
+    for job in idle.iter() {
+        flush_job(job).await;
+    }
+}
+
+/// スケジュールしているジョブをメトリクスに登録する．
+fn configure_metrics(jobs: &[ScheduledJob], clock: &dyn Clock) {
+    search_and_report::metrics::global().configure_jobs(
+        jobs.iter()
+            .map(|job| job.config.search_config.name.as_str()),
+        clock.now(),
+    );
+}
+
+/// アプリケーションのスケジューリングを行う．
+async fn schedule_and_run_app(
+    config: config::AllConfig,
+    limit: &ConcurrencyLimit,
+    clock: Arc<dyn Clock>,
+) -> Result<(JobScheduler, Vec<ScheduledJob>), Box<dyn std::error::Error>> {
+    let AllConfig {
+        search_and_reports, ..
+    } = config;
+
+    let scheduler = JobScheduler::new().await?;
+    let mut jobs = Vec::new();
+
+    for search_and_report_config in search_and_reports.into_iter() {
+        let job = prepare_job(search_and_report_config, Arc::clone(&clock))?;
+        jobs.push(
+            job.start(&scheduler, limit.clone(), Arc::clone(&clock), None)
+                .await?,
+        );
+    }
+
+    Ok((scheduler, jobs))
+}
+
+/// スケジューラーを止め，実行中のジョブの完了を`timeout`まで待ってからリポーターの状態を書き出す．
+/// 時間内に完了しなかったジョブの数を返す．
+async fn shutdown_jobs(
+    scheduler: &mut JobScheduler,
+    jobs: &[ScheduledJob],
+    timeout: std::time::Duration,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    for job in jobs.iter() {
+        job.handle.stop(scheduler).await?;
+    }
+    scheduler.shutdown().await?;
+
+    let idle = futures::future::join_all(jobs.iter().map(|job| job.handle.wait_idle()));
+    if tokio::time::timeout(timeout, idle).await.is_err() {
+        for job in jobs.iter().filter(|job| job.handle.is_running()) {
+            tracing::warn!(
+                "Job {} did not finish within {:?}.",
+                job.config.search_config.name,
+                timeout
+            );
+        }
+    }
+
+    for job in jobs.iter() {
+        flush_job(job).await;
+    }
+
+    Ok(jobs.iter().filter(|job| job.handle.is_running()).count())
+}
+
+/// SIGINTかSIGTERMを受け取るまで待つ．
+async fn shutdown_signal() -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            res = tokio::signal::ctrl_c() => res,
+            _ = terminate.recv() => Ok(()),
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await
+}
+
+/// configファイルの変更を確認する間隔
+const CONFIG_WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// configファイルを読み込む．`include`したものを含め，読み込んだファイルのパスも返す．
+fn read_config(
+    path: &std::path::Path,
+) -> Result<(AllConfig, Vec<std::path::PathBuf>), ConfigError> {
+    let (root, files) = config::load_value(path)?;
+    let all_config = serde_path_to_error::deserialize(root)
+        .map_err(|e| ConfigError(format!("{:?}: {}: {}", path, e.path(), e.inner())))?;
+    config::check_names(&all_config).map_err(|e| ConfigError(format!("{:?}: {}", path, e.0)))?;
+    Ok((all_config, files))
+}
+
+/// configファイルの更新日時．取得できない場合はNone．
+fn config_modified(paths: &[std::path::PathBuf]) -> Vec<Option<std::time::SystemTime>> {
+    paths
+        .iter()
+        .map(|path| {
+            std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}
+
+use clap::Parser;
+
+/// ジョブの実行に失敗した場合の終了コード
+const EXIT_JOB_FAILED: u8 = 3;
+
+/// 終了時に実行中のジョブが時間内に完了しなかった場合の終了コード
+const EXIT_SHUTDOWN_TIMEOUT: u8 = 4;
+
+/// 終了時に実行中のジョブの完了を待つデフォルトの秒数
+const SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+
+/// 表に含める内容の文字数
+const TABLE_CONTENT_LENGTH: usize = 60;
+
+#[derive(Debug, Parser)]
+struct Arg {
+    /// config file path.
+    #[arg(short, long, global = true)]
+    config: Option<String>,
+
+    /// search and report immediately. same as `daemon -i`.
+    #[arg(short, long, default_value_t = false)]
+    instant: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+impl Arg {
+    /// 実行するサブコマンド．省略した場合はデーモンとし，`-i`はデーモンの`-i`として扱う．
+    fn into_command(self) -> Result<Command, clap::Error> {
+        match self.command {
+            None => Ok(Command::Daemon {
+                instant: self.instant,
+                shutdown_timeout: SHUTDOWN_TIMEOUT_SECS,
+                listen: None,
+                selection: JobSelection::default(),
+            }),
+            Some(Command::Daemon {
+                instant,
+                shutdown_timeout,
+                listen,
+                selection,
+            }) => Ok(Command::Daemon {
+                instant: instant || self.instant,
+                shutdown_timeout,
+                listen,
+                selection,
+            }),
+            Some(_) if self.instant => Err(clap::Error::raw(
+                clap::error::ErrorKind::ArgumentConflict,
+                "-i/--instant is only available for daemon.\n",
+            )),
+            Some(command) => Ok(command),
+        }
+    }
+}
+
+/// 実行するジョブの選択
+#[derive(Debug, Default, Clone, clap::Args)]
+struct JobSelection {
+    /// run only the named job. can be repeated. runs the job even if it is disabled.
+    #[arg(long = "job", value_name = "NAME")]
+    jobs: Vec<String>,
+    /// skip the named job. can be repeated.
+    #[arg(long = "skip", value_name = "NAME")]
+    skips: Vec<String>,
+}
+
+impl JobSelection {
+    /// `--job`で指定した場合は無効なジョブも実行する．
+    fn is_selected(&self, config: &SearchAndReportConfig) -> bool {
+        let name = &config.search_config.name;
+        if self.skips.contains(name) {
+            false
+        } else if self.jobs.is_empty() {
+            config.enabled
+        } else {
+            self.jobs.contains(name)
+        }
+    }
+
+    /// 選択したジョブのみを残す．存在しない名前を指定した場合はエラーとなる．
+    fn select(&self, mut config: AllConfig) -> Result<AllConfig, ConfigError> {
+        let unknown = self
+            .jobs
+            .iter()
+            .chain(self.skips.iter())
+            .filter(|name| {
+                !config
+                    .search_and_reports
+                    .iter()
+                    .any(|job| &job.search_config.name == *name)
+            })
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        if !unknown.is_empty() {
+            return Err(ConfigError(format!(
+                "Unknown jobs: {}.",
+                unknown.join(", ")
+            )));
+        }
+
+        config
+            .search_and_reports
+            .retain(|job| self.is_selected(job));
+        Ok(config)
+    }
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Command {
+    /// run the scheduler and reload the config file on change. (default)
+    Daemon {
+        /// search and report immediately
+        #[arg(short, long, default_value_t = false)]
+        instant: bool,
+        /// seconds to wait for running jobs on SIGINT or SIGTERM. exits with 4 if any are left.
+        #[arg(long, value_name = "SECS", default_value_t = SHUTDOWN_TIMEOUT_SECS)]
+        shutdown_timeout: u64,
+        /// address to serve the http api for job status and control on. e.g. 127.0.0.1:8080
+        #[arg(long, value_name = "ADDR")]
+        listen: Option<std::net::SocketAddr>,
+        #[command(flatten)]
+        selection: JobSelection,
+    },
+    /// search and report once and exit. exits with 3 if any job failed.
+    Once {
+        #[command(flatten)]
+        selection: JobSelection,
+    },
+    /// search and evaluate the conditions without reporting.
+    DryRun {
+        #[command(flatten)]
+        selection: JobSelection,
+    },
+    /// list the jobs with their next fire times.
+    List,
+    /// parse a saved search result page and print the posts.
+    Parse {
+        /// html file of the search result.
+        file: std::path::PathBuf,
+        /// platform of the page.
+        #[arg(short, long, default_value = "YahooJp", value_parser = parse_platform)]
+        platform: PlatForm,
+        /// output format.
+        #[arg(short, long, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// validate the config file and report every problem. writes nothing unless --check-writable is given.
+    Validate {
+        /// create and remove a probe file in each report directory to check that it is writable.
+        #[arg(long, default_value_t = false)]
+        check_writable: bool,
+    },
+}
+
+/// `parse`の出力形式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+fn parse_platform(value: &str) -> Result<PlatForm, ConfigError> {
+    PlatForm::try_from(value.to_string())
+}
+
+/// ポストを表として整形する．
+fn posts_table(posts: &[search_and_report::Post]) -> String {
+    let author_width = posts
+        .iter()
+        .map(|post| post.author.chars().count())
+        .max()
+        .unwrap_or_default()
+        .max("author".len());
+
+    let mut table = format!(
+        "{:<19}  {:<author_width$}  {}\n",
+        "datetime", "author", "content"
+    );
+    for post in posts.iter() {
+        let datetime = match post.time {
+            Some(time) => format!("{} {}", post.date, time.format("%H:%M:%S")),
+            None => post.date.to_string(),
+        };
+        let content = post
+            .content
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .take(TABLE_CONTENT_LENGTH)
+            .collect::<String>();
+        table.push_str(&format!(
+            "{:<19}  {:<author_width$}  {}\n",
+            datetime, post.author, content
+        ));
+    }
+    table
+}
+
+/// 保存したページをパースして出力する．
+fn parse_file(
+    path: &std::path::Path,
+    platform: PlatForm,
+    format: OutputFormat,
+) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    use search_and_report::PostParser;
+
+    let source = std::fs::read_to_string(path)?;
+    let output = match platform {
+        PlatForm::YahooJp(_) => {
+            <YahooJp as search_and_report::PlatForm>::Parser::parse_partial(source, &SystemClock)?
+        }
+    };
+
+    match format {
+        OutputFormat::Table => {
+            print!("{}", posts_table(&output.posts));
+            for diagnostic in output.diagnostics.iter() {
+                eprintln!("{}", diagnostic);
+            }
+        }
+        OutputFormat::Json => {
+            let output = serde_json::json!({
+                "page_status": output.page_status,
+                "posts": output.posts,
+                "diagnostics": output.diagnostics,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// ジョブと次回の実行日時を出力する．
+fn list_jobs(
+    config_path: &std::path::Path,
+    clock: &dyn Clock,
+) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let (all_config, _) = read_config(config_path)?;
+
+    let rows = all_config
+        .search_and_reports
+        .iter()
+        .map(|config| {
+            let timezone = config.timezone.unwrap_or(chrono_tz::UTC);
+            let next = match CronSchedule::new(&config.cron, timezone) {
+                Ok(schedule) => schedule
+                    .next_after(clock.now())
+                    .map(|next| next.with_timezone(&timezone).to_string())
+                    .unwrap_or("-".to_string()),
+                Err(e) => e.to_string(),
+            };
+            [
+                config.search_config.name.clone(),
+                config.enabled.to_string(),
+                config.cron.clone(),
+                timezone.to_string(),
+                next,
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let header = ["job", "enabled", "cron", "timezone", "next"].map(str::to_string);
+    let widths = (0..header.len())
+        .map(|i| {
+            std::iter::once(&header)
+                .chain(rows.iter())
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    for row in std::iter::once(&header).chain(rows.iter()) {
+        let line = row
+            .iter()
+            .zip(widths.iter())
+            .map(|(column, width)| format!("{:<width$}", column, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// 選択したジョブを一度だけ実行する．`dry_run`の場合はリポートしない．
+async fn run_once(
+    config_path: &std::path::Path,
+    selection: &JobSelection,
+    dry_run: bool,
+    clock: Arc<dyn Clock>,
+) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let (all_config, _) = read_config(config_path)?;
+    let all_config = selection.select(all_config)?;
+
+    // 実行前に全てのジョブを作製し，設定の誤りを先に検出する
+    let jobs = all_config
+        .search_and_reports
+        .into_iter()
+        .map(|config| prepare_job(config, Arc::clone(&clock)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut failed = 0;
+    for job in jobs.iter() {
+        let label = &job.config.search_config.name;
+        let res = if dry_run {
+            job.task.dry_run().instrument(job.task.span()).await
+        } else {
+            job.task.run().instrument(job.task.span()).await
+        };
+
+        match res {
+            Ok(outcome) => {
+                let verdicts = outcome
+                    .verdicts
+                    .iter()
+                    .map(|verdict| {
+                        let result = if verdict.matched {
+                            "matched"
+                        } else {
+                            "not matched"
+                        };
+                        format!("{}: {}", verdict.name, result)
+                    })
+                    .collect::<Vec<_>>();
+                println!(
+                    "{}: {} posts. {}",
+                    label,
+                    outcome.posts.len(),
+                    verdicts.join(", ")
+                );
+            }
+            Err(e) => {
+                failed += 1;
+                println!("{}: failed. {}", label, e);
+            }
+        }
+    }
+
+    if failed == 0 {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::from(EXIT_JOB_FAILED))
+    }
+}
+
+/// configファイルを検証して問題を出力する．
+fn validate_config(
+    config_path: &std::path::Path,
+    check_writable: bool,
+) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let diagnostics = validate::validate(config_path, check_writable);
+    for diagnostic in diagnostics.iter() {
+        println!("{}", diagnostic);
+    }
+    if !diagnostics.is_empty() {
+        return Err(ConfigError(format!(
+            "{} problems found in {:?}.",
+            diagnostics.len(),
+            config_path
+        ))
+        .into());
+    }
+    println!("{:?} is valid.", config_path);
+    Ok(ExitCode::SUCCESS)
+}
+
+/// スケジューラーを起動し，SIGINTかSIGTERMを受け取るまでconfigファイルの変更を監視し続ける．
+async fn daemon(
+    config_path: &std::path::Path,
+    selection: &JobSelection,
+    instant: bool,
+    shutdown_timeout: std::time::Duration,
+    listen: Option<std::net::SocketAddr>,
+    clock: Arc<dyn Clock>,
+) -> Result<ExitCode, Box<dyn std::error::Error>> {
+    /// configファイルの読み取りかデフォルトの作製．
+    fn read_or_create_config(
+        path: &std::path::Path,
+    ) -> Result<(AllConfig, Vec<std::path::PathBuf>), Box<dyn std::error::Error>> {
+        if path.is_file() {
+            // ファイルの場合
+            Ok(read_config(path)?)
+        } else {
+            // ファイルでない場合．拡張子に応じた形式で作製する．
+            use std::io::Write;
+
+            info!("Creating default config file into {:?}.", path);
+            let default_config = AllConfig::default();
+            let format = ConfigFormat::from_path(path)?;
+
+            let mut file = std::fs::File::create(path)?;
+            file.write_all(format.serialize(&default_config)?.as_bytes())?;
+
+            Ok((default_config, vec![path.to_path_buf()]))
+        }
+    }
+
+    // 起動中に受け取ったシグナルも取りこぼさないように先に待ち始める
+    let mut signal = tokio::spawn(shutdown_signal());
+
+    let (all_config, mut config_files) = read_or_create_config(config_path)?;
+    let all_config = selection.select(all_config)?;
+
+    // ジョブを開始する前に待ち受けを始め，アドレスの誤りを先に検出する
+    let shared_jobs = api::SharedJobs::default();
+    let (stop_api, api_stopped) = tokio::sync::oneshot::channel::<()>();
+    let api = match listen {
+        Some(addr) => {
+            let server = api::serve(
+                addr,
+                all_config.api_token.clone(),
+                Arc::clone(&shared_jobs),
+                async {
+                    let _ = api_stopped.await;
+                },
+            )?;
+            info!("Serving the api on http://{}.", addr);
+            Some(tokio::spawn(server))
+        }
+        None => None,
+    };
+
+    // 同時実行数の上限とAPIのトークンは起動時の設定に従う
+    let max_concurrent_jobs = all_config.max_concurrent_jobs;
+    let api_token = all_config.api_token.clone();
+    let limit = concurrency_limit(&all_config);
+    let (mut scheduler, mut jobs) =
+        schedule_and_run_app(all_config, &limit, Arc::clone(&clock)).await?;
+    configure_metrics(&jobs, clock.as_ref());
+    *shared_jobs.write().unwrap() = jobs.clone();
+
+    info!("scheduler started.");
+    scheduler.start().await?;
+
+    // 即時実行は実行中として記録し，シグナルを待つ間に並行して行う
+    if instant {
+        info!("search and report immediately.");
+        for job in jobs.iter() {
+            job.trigger();
+        }
+    }
+    // 再読み込みで削除したジョブのうち実行中のもの
+    let mut retired = Vec::new();
+
+    // configファイルの変更を監視し，変更のあったジョブのみを入れ替える
+    let mut last_modified = config_modified(&config_files);
+    loop {
+        tokio::select! {
+            res = &mut signal => {
+                res??;
+                break;
+            }
+            _ = tokio::time::sleep(CONFIG_WATCH_INTERVAL) => {}
+        }
+        flush_retired(&mut retired).await;
+
+        let modified = config_modified(&config_files);
+        if modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+
+        info!("Config file changed. Reloading {:?}.", config_path);
+        let res = match read_config(config_path) {
+            Ok((all_config, files)) => {
+                // `include`の変更に合わせて監視するファイルを更新する
+                config_files = files;
+                last_modified = config_modified(&config_files);
+                if all_config.max_concurrent_jobs != max_concurrent_jobs {
+                    tracing::warn!("max_concurrent_jobs is applied after restarting.");
+                }
+                if all_config.api_token != api_token {
+                    tracing::warn!("api_token is applied after restarting.");
+                }
+                match selection.select(all_config) {
+                    Ok(all_config) => {
+                        reload_jobs(
+                            &scheduler,
+                            &mut jobs,
+                            &mut retired,
+                            all_config,
+                            &limit,
+                            Arc::clone(&clock),
+                        )
+                        .await
+                    }
+                    Err(e) => Err(e.into()),
+                }
+            }
+            Err(e) => Err(e.into()),
+        };
+        match res {
+            Ok(()) => info!("Config reloaded. {} jobs are scheduled.", jobs.len()),
+            Err(e) => tracing::error!("Failed to reload config. Keeping the current jobs. {}", e),
+        }
+        configure_metrics(&jobs, clock.as_ref());
+        *shared_jobs.write().unwrap() = jobs.clone();
+    }
+
+    // 終了中に新たな実行を受け付けないように先にapiを止める
+    let _ = stop_api.send(());
+    if let Some(api) = api {
+        if let Err(e) = api.await? {
+            tracing::error!("Api server failed. {}", e);
+        }
+    }
+
+    info!("Shutting down. Waiting for running jobs.");
+    // 削除したジョブの実行中の検索・リポートも待ち，フラッシュする
+    jobs.extend(retired);
+    let unfinished = shutdown_jobs(&mut scheduler, &jobs, shutdown_timeout).await?;
+    if unfinished == 0 {
+        info!("Shut down.");
+        Ok(ExitCode::SUCCESS)
+    } else {
+        tracing::warn!("Shut down with {} unfinished jobs.", unfinished);
+        Ok(ExitCode::from(EXIT_SHUTDOWN_TIMEOUT))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    use tracing_subscriber::FmtSubscriber;
+
+    let arg = Arg::parse();
+    let config = arg.config.clone();
+    let command = arg.into_command().unwrap_or_else(|e| e.exit());
+
+    // tracing．デーモン以外では出力と混ざらないように標準エラー出力に書き込む
+    let builder = FmtSubscriber::builder().with_max_level(tracing::Level::INFO);
+    if let Command::Daemon { .. } = command {
+        tracing::subscriber::set_global_default(builder.finish())?;
+    } else {
+        tracing::subscriber::set_global_default(builder.with_writer(std::io::stderr).finish())?;
+    }
+
+    // パスが与えられていない場合はデフォルトのパス
+    let config_path =
+        std::path::PathBuf::from(config.unwrap_or("./default_config.json".to_string()));
+    let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+
+    match command {
+        Command::Daemon {
+            instant,
+            shutdown_timeout,
+            listen,
+            selection,
+        } => {
+            let shutdown_timeout = std::time::Duration::from_secs(shutdown_timeout);
+            daemon(
+                &config_path,
+                &selection,
+                instant,
+                shutdown_timeout,
+                listen,
+                clock,
+            )
+            .await
+        }
+        Command::Once { selection } => run_once(&config_path, &selection, false, clock).await,
+        Command::DryRun { selection } => run_once(&config_path, &selection, true, clock).await,
+        Command::List => list_jobs(&config_path, clock.as_ref()),
+        Command::Parse {
+            file,
+            platform,
+            format,
+        } => parse_file(&file, platform, format),
+        Command::Validate { check_writable } => validate_config(&config_path, check_writable),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::config::OverlapPolicy;
+    use super::config::{AllConfig, SearchAndReportConfig};
+    use super::{
+        flush_retired, posts_table, reload_jobs, schedule_and_run_app, shutdown_jobs, Arg, Command,
+        JobHandle, OutputFormat,
+    };
+    use search_and_report::{
+        clock::{Clock, FixedClock},
+        Post, SearchConfig,
+    };
+
+    use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+    use clap::Parser;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    pub(crate) fn job_config(keyword: &str, cron: &str) -> SearchAndReportConfig {
+        SearchAndReportConfig {
+            search_config: SearchConfig {
+                name: keyword.to_lowercase(),
+                keywords: vec![keyword.to_string()],
+                ..Default::default()
+            },
+            cron: cron.to_string(),
+            report_json_dir: None,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reload_jobs() {
+        let clock: Arc<dyn Clock> = Arc::new(FixedClock::new(
+            Utc.with_ymd_and_hms(2023, 11, 27, 0, 0, 0).unwrap(),
+        ));
+        let config = AllConfig {
+            include: Vec::new(),
+            max_concurrent_jobs: None,
+            api_token: None,
+            search_and_reports: vec![
+                job_config("Rust", "0 0 6 * * * *"),
+                job_config("Go", "0 0 6 * * * *"),
+            ],
+        };
+        let (scheduler, mut jobs) = schedule_and_run_app(config, &None, Arc::clone(&clock))
+            .await
+            .unwrap();
+        let rust = jobs[0].handle.clone();
+        let go = jobs[1].handle.clone();
+        go.pause();
+        go.status.lock().unwrap().last_run = Some(clock.now());
+        let mut retired = Vec::new();
+        let running = go.begin_run();
+
+        // 変更のあったジョブのみ入れ替える
+        let config = AllConfig {
+            include: Vec::new(),
+            max_concurrent_jobs: None,
+            api_token: None,
+            search_and_reports: vec![
+                job_config("Go", "0 0 12 * * * *"),
+                job_config("Rust", "0 0 6 * * * *"),
+            ],
+        };
+        reload_jobs(
+            &scheduler,
+            &mut jobs,
+            &mut retired,
+            config,
+            &None,
+            Arc::clone(&clock),
+        )
+        .await
+        .unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert!(Arc::ptr_eq(&jobs[0].handle.stopped, &rust.stopped));
+        assert!(!rust.is_stopped());
+        assert!(go.is_stopped());
+        assert_eq!(jobs[1].config.cron, "0 0 12 * * * *");
+        // 置き換えたジョブの実行と重ならないようにロックを引き継ぐ
+        assert!(Arc::ptr_eq(&jobs[1].handle.exclusive, &go.exclusive));
+        assert!(!Arc::ptr_eq(&jobs[1].handle.exclusive, &rust.exclusive));
+        // 一時停止と前回の実行の結果を引き継ぎ，次回の実行は新しいスケジュールに従う
+        assert!(jobs[1].handle.is_paused());
+        let status = jobs[1].handle.status();
+        assert_eq!(status.last_run, Some(clock.now()));
+        assert_eq!(
+            status.next_run,
+            Some(Utc.with_ymd_and_hms(2023, 11, 27, 12, 0, 0).unwrap())
+        );
+
+        // 削除したジョブは実行中の間は残す
+        assert_eq!(retired.len(), 1);
+        flush_retired(&mut retired).await;
+        assert_eq!(retired.len(), 1);
+        drop(running);
+        flush_retired(&mut retired).await;
+        assert!(retired.is_empty());
+
+        // 不正な設定の場合は実行中のジョブを残す
+        let config = AllConfig {
+            include: Vec::new(),
+            max_concurrent_jobs: None,
+            api_token: None,
+            search_and_reports: vec![job_config("Rust", "every day")],
+        };
+        assert!(reload_jobs(
+            &scheduler,
+            &mut jobs,
+            &mut retired,
+            config,
+            &None,
+            Arc::clone(&clock)
+        )
+        .await
+        .is_err());
+        assert_eq!(jobs.len(), 2);
+        assert!(jobs.iter().all(|job| !job.handle.is_stopped()));
+    }
+
+    #[tokio::test]
+    async fn test_job_handle_run() {
+        // 同時に実行している数とその最大値を記録し，`delay`だけかかる実行
+        let active = Arc::new(AtomicUsize::new(0));
+        let max_active = Arc::new(AtomicUsize::new(0));
+        let finished = Arc::new(AtomicUsize::new(0));
+        let job = |delay: u64| {
+            let active = Arc::clone(&active);
+            let max_active = Arc::clone(&max_active);
+            let finished = Arc::clone(&finished);
+            async move {
+                let current = active.fetch_add(1, Ordering::SeqCst) + 1;
+                max_active.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                active.fetch_sub(1, Ordering::SeqCst);
+                finished.fetch_add(1, Ordering::SeqCst);
+            }
+        };
+        // 一回目の実行中に二回目を実行する
+        let overlap = |handle: JobHandle| async move {
+            let second = async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                handle.run(job(0)).await
+            };
+            tokio::join!(handle.run(job(100)), second);
+        };
+
+        overlap(JobHandle::new(OverlapPolicy::Skip, None)).await;
+        assert_eq!(finished.swap(0, Ordering::SeqCst), 1);
+
+        overlap(JobHandle::new(OverlapPolicy::Queue, None)).await;
+        assert_eq!(finished.swap(0, Ordering::SeqCst), 2);
+        assert_eq!(max_active.swap(0, Ordering::SeqCst), 1);
+
+        // 中断された一回目は完了しない
+        overlap(JobHandle::new(OverlapPolicy::Cancel, None)).await;
+        assert_eq!(finished.swap(0, Ordering::SeqCst), 1);
+        assert_eq!(active.swap(0, Ordering::SeqCst), 1);
+
+        // 異なるジョブでも全体の上限を超えて同時に実行しない
+        let limit = Some(Arc::new(tokio::sync::Semaphore::new(1)));
+        let rust = JobHandle::new(OverlapPolicy::Skip, limit.clone());
+        let go = JobHandle::new(OverlapPolicy::Skip, limit);
+        max_active.store(0, Ordering::SeqCst);
+        tokio::join!(rust.run(job(50)), go.run(job(50)));
+        assert_eq!(finished.load(Ordering::SeqCst), 2);
+        assert_eq!(max_active.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_jobs() {
+        let clock: Arc<dyn Clock> = Arc::new(FixedClock::new(
+            Utc.with_ymd_and_hms(2023, 11, 27, 0, 0, 0).unwrap(),
+        ));
+        let config = AllConfig {
+            include: Vec::new(),
+            max_concurrent_jobs: None,
+            api_token: None,
+            search_and_reports: vec![
+                job_config("Rust", "0 0 6 * * * *"),
+                job_config("Go", "0 0 6 * * * *"),
+            ],
+        };
+        let (mut scheduler, jobs) = schedule_and_run_app(config, &None, Arc::clone(&clock))
+            .await
+            .unwrap();
+
+        // 期限内に完了した実行は待つ
+        let running = jobs[0].handle.begin_run();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(running);
+        });
+        // 期限を過ぎても完了しない実行は待たない
+        let _stuck = jobs[1].handle.begin_run();
+
+        let unfinished = shutdown_jobs(&mut scheduler, &jobs, Duration::from_millis(500))
+            .await
+            .unwrap();
+        assert_eq!(unfinished, 1);
+        assert!(!jobs[0].handle.is_running());
+        assert!(jobs.iter().all(|job| job.handle.is_stopped()));
+    }
+
+    #[tokio::test]
+    async fn test_wake_job() {
+        let now = Utc.with_ymd_and_hms(2023, 11, 27, 0, 0, 0).unwrap();
+        let clock: Arc<dyn Clock> = Arc::new(FixedClock::new(now));
+        let config = AllConfig {
+            include: Vec::new(),
+            max_concurrent_jobs: None,
+            api_token: None,
+            search_and_reports: vec![job_config("Rust", "* * * * * * *")],
+        };
+        let (scheduler, jobs) = schedule_and_run_app(config, &None, Arc::clone(&clock))
+            .await
+            .unwrap();
+        scheduler.start().await.unwrap();
+        let handle = jobs[0].handle.clone();
+        let job_id = *handle.job_id.lock().unwrap();
+        let next = now + chrono::Duration::seconds(1);
+        assert_eq!(handle.status().next_run, Some(next));
+
+        // 単調時計で1秒経っても壁時計が実行の日時に達していなければ実行せずに待ち直す
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert_ne!(*handle.job_id.lock().unwrap(), job_id);
+        assert_eq!(handle.status().last_run, None);
+        assert_eq!(handle.status().next_run, Some(next));
+    }
+
+    #[test]
+    fn test_cli() {
+        let arg = Arg::try_parse_from(["main", "parse", "row.html", "--format", "json"]).unwrap();
+        match arg.command {
+            Some(Command::Parse { file, format, .. }) => {
+                assert_eq!(file, std::path::PathBuf::from("row.html"));
+                assert_eq!(format, OutputFormat::Json);
+            }
+            command => panic!("unexpected command: {:?}", command),
+        }
+        assert!(Arg::try_parse_from(["main", "parse", "row.html", "-p", "Twitter"]).is_err());
+
+        let arg = Arg::try_parse_from(["main", "daemon", "-i", "-c", "config.toml"]).unwrap();
+        assert!(matches!(
+            arg.command,
+            Some(Command::Daemon { instant: true, .. })
+        ));
+        assert_eq!(arg.config.as_deref(), Some("config.toml"));
+
+        // サブコマンドを省略した`-i`はデーモンの`-i`
+        let command = Arg::try_parse_from(["main", "-i"])
+            .unwrap()
+            .into_command()
+            .unwrap();
+        assert!(matches!(command, Command::Daemon { instant: true, .. }));
+        assert!(Arg::try_parse_from(["main", "-i", "once"])
+            .unwrap()
+            .into_command()
+            .is_err());
+
+        // ジョブの選択
+        let config = AllConfig {
+            include: Vec::new(),
+            max_concurrent_jobs: None,
+            api_token: None,
+            search_and_reports: vec![
+                job_config("Rust", "0 0 6 * * * *"),
+                SearchAndReportConfig {
+                    enabled: false,
+                    ..job_config("Go", "0 0 6 * * * *")
+                },
+                job_config("Zig", "0 0 6 * * * *"),
+            ],
+        };
+        let selected = |args: &[&str]| {
+            let selection = match Arg::try_parse_from(args).unwrap().command {
+                Some(Command::Once { selection }) => selection,
+                command => panic!("unexpected command: {:?}", command),
+            };
+            selection.select(config.clone()).map(|config| {
+                config
+                    .search_and_reports
+                    .into_iter()
+                    .map(|job| job.search_config.name)
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(selected(&["main", "once"]).unwrap(), ["rust", "zig"]);
+        assert_eq!(
+            selected(&["main", "once", "--job", "go", "--job", "zig"]).unwrap(),
+            ["go", "zig"]
+        );
+        assert_eq!(
+            selected(&["main", "once", "--skip", "rust"]).unwrap(),
+            ["zig"]
+        );
+        assert!(selected(&["main", "once", "--job", "java"]).is_err());
+
+        let posts = vec![Post {
+            id: "1".to_string(),
+            author: "alice".to_string(),
+            date: NaiveDate::from_ymd_opt(2023, 11, 27).unwrap(),
+            time: Some(NaiveTime::from_hms_opt(12, 0, 0).unwrap()),
+            content: "Rust\nCLI".to_string(),
+        }];
+        assert_eq!(
+            posts_table(&posts),
+            "datetime             author  content\n2023-11-27 12:00:00  alice   Rust CLI\n"
+        );
+    }
+}
@@ -0,0 +1,941 @@
+use super::config::{includes, interpolate_env, ConfigFormat, SearchAndReportConfig};
+use search_and_report::{
+    reporter::{EmailReporter, ReportTemplate, WebhookReporter},
+    schedule::CronSchedule,
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter::Peekable;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::str::Chars;
+
+/// 設定ファイルの問題
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub file: PathBuf,
+    /// jsonのパス(例: `search_and_reports[0].cron`)．全体の場合は空．
+    pub path: String,
+    /// 行番号．特定できない場合はNone．
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:", self.file.display())?;
+        if let Some(line) = self.line {
+            write!(f, "{}:", line)?;
+        }
+        write!(f, " ")?;
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// jsonのパスと行番号の対応を作製する．構文が正しいことを前提とする．
+struct LineIndexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    lines: BTreeMap<String, usize>,
+}
+
+impl<'a> LineIndexer<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            chars: source.chars().peekable(),
+            line: 1,
+            lines: BTreeMap::new(),
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.next();
+        }
+    }
+
+    fn string(&mut self) -> String {
+        let mut string = String::new();
+        self.next(); // "
+        while let Some(c) = self.next() {
+            match c {
+                '"' => break,
+                '\\' => string.extend(self.next()),
+                c => string.push(c),
+            }
+        }
+        string
+    }
+
+    /// 値を読み進め，パスの行番号を記録する．オブジェクトの要素はキーの行とする．
+    fn value(&mut self, path: String) {
+        self.skip_whitespace();
+        self.lines.entry(path.clone()).or_insert(self.line);
+
+        match self.chars.peek() {
+            Some('{') => {
+                self.next();
+                loop {
+                    self.skip_whitespace();
+                    match self.chars.peek() {
+                        Some('"') => {
+                            let key = self.string();
+                            let child = if path.is_empty() {
+                                key
+                            } else {
+                                format!("{}.{}", path, key)
+                            };
+                            self.lines.entry(child.clone()).or_insert(self.line);
+                            self.skip_whitespace();
+                            self.next(); // :
+                            self.value(child);
+                        }
+                        Some(',') => {
+                            self.next();
+                        }
+                        _ => {
+                            self.next(); // }
+                            break;
+                        }
+                    }
+                }
+            }
+            Some('[') => {
+                self.next();
+                let mut i = 0;
+                loop {
+                    self.skip_whitespace();
+                    match self.chars.peek() {
+                        Some(']') | None => {
+                            self.next();
+                            break;
+                        }
+                        Some(',') => {
+                            self.next();
+                        }
+                        _ => {
+                            self.value(format!("{}[{}]", path, i));
+                            i += 1;
+                        }
+                    }
+                }
+            }
+            Some('"') => {
+                self.string();
+            }
+            _ => {
+                while self
+                    .chars
+                    .peek()
+                    .is_some_and(|c| !c.is_whitespace() && !",]}".contains(*c))
+                {
+                    self.next();
+                }
+            }
+        }
+    }
+}
+
+/// バイト位置の行番号
+fn line_at(source: &str, index: usize) -> usize {
+    source[..index.min(source.len())].matches('\n').count() + 1
+}
+
+/// TOMLの値を位置とともに読み込んだ木
+enum TomlNode {
+    Table(Vec<(toml::Spanned<String>, toml::Spanned<TomlNode>)>),
+    Array(Vec<toml::Spanned<TomlNode>>),
+    Other,
+}
+
+impl<'de> Deserialize<'de> for TomlNode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NodeVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for NodeVisitor {
+            type Value = TomlNode;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a TOML value")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<TomlNode, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(key) = map.next_key()? {
+                    entries.push((key, map.next_value()?));
+                }
+                Ok(TomlNode::Table(entries))
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<TomlNode, A::Error> {
+                let mut elements = Vec::new();
+                while let Some(element) = seq.next_element()? {
+                    elements.push(element);
+                }
+                Ok(TomlNode::Array(elements))
+            }
+
+            fn visit_bool<E>(self, _: bool) -> Result<TomlNode, E> {
+                Ok(TomlNode::Other)
+            }
+
+            fn visit_i64<E>(self, _: i64) -> Result<TomlNode, E> {
+                Ok(TomlNode::Other)
+            }
+
+            fn visit_u64<E>(self, _: u64) -> Result<TomlNode, E> {
+                Ok(TomlNode::Other)
+            }
+
+            fn visit_f64<E>(self, _: f64) -> Result<TomlNode, E> {
+                Ok(TomlNode::Other)
+            }
+
+            fn visit_str<E>(self, _: &str) -> Result<TomlNode, E> {
+                Ok(TomlNode::Other)
+            }
+        }
+
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+/// TOMLのパスと行番号の対応を作製する．テーブルの要素はキーの行とする．
+fn toml_lines(source: &str) -> BTreeMap<String, usize> {
+    fn walk(
+        source: &str,
+        node: &toml::Spanned<TomlNode>,
+        path: String,
+        lines: &mut BTreeMap<String, usize>,
+    ) {
+        lines
+            .entry(path.clone())
+            .or_insert_with(|| line_at(source, node.span().start));
+        match node.get_ref() {
+            TomlNode::Table(entries) => {
+                for (key, value) in entries.iter() {
+                    let child = if path.is_empty() {
+                        key.get_ref().clone()
+                    } else {
+                        format!("{}.{}", path, key.get_ref())
+                    };
+                    lines.insert(child.clone(), line_at(source, key.span().start));
+                    walk(source, value, child, lines);
+                }
+            }
+            TomlNode::Array(elements) => {
+                for (i, element) in elements.iter().enumerate() {
+                    walk(source, element, format!("{}[{}]", path, i), lines);
+                }
+            }
+            TomlNode::Other => {}
+        }
+    }
+
+    let mut lines = BTreeMap::new();
+    if let Ok(root) = toml::from_str::<toml::Spanned<TomlNode>>(source) {
+        walk(source, &root, String::new(), &mut lines);
+    }
+    lines
+}
+
+/// YAMLのブロック形式のマッピング・シーケンスの入れ子
+struct YamlBlock {
+    indent: usize,
+    path: String,
+    /// シーケンスの場合は次の要素の番号
+    next_index: Option<usize>,
+}
+
+/// YAMLのパスと行番号の対応を作製する．ブロック形式のマッピング・シーケンスのみを辿り，
+/// フロー形式(`[...]`, `{...}`)の中身は親のパスの行とする．構文が正しいことを前提とする．
+fn yaml_lines(source: &str) -> BTreeMap<String, usize> {
+    let mut lines = BTreeMap::new();
+    let mut blocks: Vec<YamlBlock> = Vec::new();
+    // 値が次の行以降に続くキー・要素のパス
+    let mut pending = String::new();
+    // 複数行のスカラー(`|`, `>`)を持つキーのインデント
+    let mut block_scalar: Option<usize> = None;
+
+    for (i, raw) in source.lines().enumerate() {
+        let line = i + 1;
+        let trimmed = raw.trim_start();
+        let mut indent = raw.len() - trimmed.len();
+        if let Some(scalar_indent) = block_scalar {
+            if trimmed.is_empty() || indent > scalar_indent {
+                continue;
+            }
+            block_scalar = None;
+        }
+        if trimmed.is_empty()
+            || trimmed.starts_with('#')
+            || trimmed.starts_with("---")
+            || trimmed.starts_with("...")
+        {
+            continue;
+        }
+
+        let mut rest = trimmed;
+        loop {
+            let is_item = rest == "-" || rest.starts_with("- ");
+            // 同じインデントのシーケンスは要素が続く間のみ有効
+            while blocks.last().is_some_and(|block| {
+                block.indent > indent
+                    || (block.indent == indent && block.next_index.is_some() && !is_item)
+            }) {
+                blocks.pop();
+            }
+
+            if is_item {
+                if !blocks
+                    .last()
+                    .is_some_and(|block| block.indent == indent && block.next_index.is_some())
+                {
+                    blocks.push(YamlBlock {
+                        indent,
+                        path: pending.clone(),
+                        next_index: Some(0),
+                    });
+                }
+                let block = blocks.last_mut().expect("block is pushed above");
+                let index = block.next_index.get_or_insert(0);
+                let path = format!("{}[{}]", block.path, index);
+                *index += 1;
+                lines.insert(path.clone(), line);
+                pending = path;
+
+                // 要素の内容が同じ行に続く場合は，その位置をインデントとして読み進める
+                let content = rest[1..].trim_start();
+                indent += rest.len() - content.len();
+                rest = content;
+                if rest.is_empty() {
+                    break;
+                }
+                continue;
+            }
+
+            let Some((key, value)) = yaml_key_value(rest) else {
+                break;
+            };
+            if !blocks
+                .last()
+                .is_some_and(|block| block.indent == indent && block.next_index.is_none())
+            {
+                blocks.push(YamlBlock {
+                    indent,
+                    path: pending.clone(),
+                    next_index: None,
+                });
+            }
+            let block = blocks.last().expect("block is pushed above");
+            let path = if block.path.is_empty() {
+                key
+            } else {
+                format!("{}.{}", block.path, key)
+            };
+            lines.insert(path.clone(), line);
+            if value.starts_with('|') || value.starts_with('>') {
+                block_scalar = Some(indent);
+            }
+            pending = path;
+            break;
+        }
+    }
+    lines
+}
+
+/// YAMLのブロック形式のマッピングの行からキーと値を取り出す．
+fn yaml_key_value(line: &str) -> Option<(String, &str)> {
+    let (key, rest) = match line.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let end = line[1..].find(quote)? + 1;
+            (line[1..end].to_string(), &line[end + 1..])
+        }
+        '[' | '{' => return None,
+        _ => {
+            let end = line
+                .find(": ")
+                .or_else(|| line.strip_suffix(':').map(str::len))?;
+            (line[..end].trim_end().to_string(), &line[end..])
+        }
+    };
+    let value = rest.trim_start().strip_prefix(':')?.trim();
+    Some((key, value))
+}
+
+/// 問題を集める．行番号はパスから特定し，無い場合は親のパスの行とする．
+struct Diagnostics {
+    file: PathBuf,
+    lines: BTreeMap<String, usize>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    fn push<S: Into<String>, M: ToString>(&mut self, path: S, message: M) {
+        let path = path.into();
+        let mut parent = path.as_str();
+        let line = loop {
+            if let Some(line) = self.lines.get(parent) {
+                break Some(*line);
+            }
+            match parent.rfind(['.', '[']) {
+                Some(i) => parent = &parent[..i],
+                None if !parent.is_empty() => parent = "",
+                None => break None,
+            }
+        };
+
+        self.diagnostics.push(Diagnostic {
+            file: self.file.clone(),
+            path,
+            line,
+            message: message.to_string(),
+        });
+    }
+}
+
+/// 位置を除いたserde_jsonのエラーメッセージ
+fn json_message(e: &serde_json::Error) -> String {
+    let message = e.to_string();
+    let position = format!(" at line {} column {}", e.line(), e.column());
+    message
+        .strip_suffix(&position)
+        .map(str::to_string)
+        .unwrap_or(message)
+}
+
+/// 元のjsonに含まれ，設定として読み込まれなかったフィールドを探す．
+fn unknown_fields(original: &Value, parsed: &Value, path: &str, fields: &mut Vec<String>) {
+    match (original, parsed) {
+        (Value::Object(original), Value::Object(parsed)) => {
+            for (key, value) in original.iter() {
+                let child = format!("{}.{}", path, key);
+                match parsed.get(key) {
+                    Some(parsed) => unknown_fields(value, parsed, &child, fields),
+                    None => fields.push(child),
+                }
+            }
+        }
+        (Value::Array(original), Value::Array(parsed)) => {
+            for (i, (value, parsed)) in original.iter().zip(parsed.iter()).enumerate() {
+                unknown_fields(value, parsed, &format!("{}[{}]", path, i), fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 保存先のディレクトリに書き込めるかを確認する．存在しない場合は作製できるかを確認する．
+/// `probe`の場合は確認用のファイルを作製・削除して確かめる．
+fn check_dir(dir_path: &Path, probe: bool) -> Result<(), String> {
+    let mut ancestor = dir_path;
+    while !ancestor.as_os_str().is_empty() && !ancestor.exists() {
+        match ancestor.parent() {
+            Some(parent) => ancestor = parent,
+            None => break,
+        }
+    }
+    if ancestor.as_os_str().is_empty() {
+        ancestor = Path::new(".");
+    }
+
+    let metadata = std::fs::metadata(ancestor)
+        .map_err(|e| format!("{:?} is not accessible. {}", ancestor, e))?;
+    if !metadata.is_dir() {
+        return Err(format!("{:?} is not a directory.", ancestor));
+    }
+    if metadata.permissions().readonly() {
+        return Err(format!("{:?} is read-only.", ancestor));
+    }
+    if !probe {
+        return Ok(());
+    }
+
+    // 読み取り専用の属性はUnixの所有者・グループやACLを反映しないため，実際にファイルを作製して確かめる
+    let probe = ancestor.join(format!(
+        ".search_and_report_validate_{}",
+        std::process::id()
+    ));
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .map_err(|e| format!("{:?} is not writable. {}", ancestor, e))?;
+    std::fs::remove_file(&probe)
+        .map_err(|e| format!("Failed to remove {:?}. Remove it manually. {}", probe, e))
+}
+
+/// ジョブの設定の値を検証する．`names`はそれまでのジョブの名前．
+fn check_job(
+    config: &SearchAndReportConfig,
+    path: &str,
+    names: &mut BTreeSet<String>,
+    check_writable: bool,
+    diagnostics: &mut Diagnostics,
+) {
+    let name = &config.search_config.name;
+    if name.trim().is_empty() {
+        diagnostics.push(format!("{}.name", path), "Empty name.");
+    } else if !names.insert(name.clone()) {
+        diagnostics.push(
+            format!("{}.name", path),
+            format!("Duplicate name: {}.", name),
+        );
+    }
+
+    if config.search_config.keywords.is_empty() {
+        diagnostics.push(format!("{}.keywords", path), "No keywords.");
+    }
+    for (i, keyword) in config.search_config.keywords.iter().enumerate() {
+        if keyword.trim().is_empty() {
+            diagnostics.push(format!("{}.keywords[{}]", path, i), "Empty keyword.");
+        }
+    }
+
+    if let Err(e) = CronSchedule::new(&config.cron, config.timezone.unwrap_or_default()) {
+        diagnostics.push(format!("{}.cron", path), e);
+    }
+
+    let mut dirs = Vec::new();
+    if let Some(dir) = config.report_json_dir.as_ref() {
+        dirs.push(("report_json_dir", Path::new(dir)));
+    }
+    if let Some(report_file) = config.report_file.as_ref() {
+        dirs.push(("report_file.dir", Path::new(&report_file.dir)));
+    }
+    if let Some(report_digest) = config.report_digest.as_ref() {
+        dirs.push(("report_digest.dir", Path::new(&report_digest.dir)));
+    }
+    if let Some(report_feed) = config.report_feed.as_ref() {
+        let parent = Path::new(&report_feed.path).parent();
+        dirs.push(("report_feed.path", parent.unwrap_or(Path::new("."))));
+    }
+    if let Some(report_sqlite_path) = config.report_sqlite_path.as_ref() {
+        let parent = Path::new(report_sqlite_path).parent();
+        dirs.push(("report_sqlite_path", parent.unwrap_or(Path::new("."))));
+    }
+    for (field, dir) in dirs.into_iter() {
+        if let Err(message) = check_dir(dir, check_writable) {
+            diagnostics.push(format!("{}.{}", path, field), message);
+        }
+    }
+
+    let mut templates = Vec::new();
+    if let Some(filename) = config
+        .report_file
+        .as_ref()
+        .and_then(|report_file| report_file.filename.as_ref())
+    {
+        templates.push(("report_file.filename", filename));
+    }
+    if let Some(summary) = config.report_os_summary.as_ref() {
+        templates.push(("report_os_summary", summary));
+    }
+    if let Some(content) = config.report_os_content.as_ref() {
+        templates.push(("report_os_content", content));
+    }
+    if let Some(content) = config.report_os_latest_content.as_ref() {
+        templates.push(("report_os_latest_content", content));
+    }
+    if let Some(report_email) = config.report_email.as_ref() {
+        templates.extend(
+            report_email
+                .subject
+                .iter()
+                .map(|s| ("report_email.subject", s)),
+        );
+        templates.extend(report_email.body.iter().map(|s| ("report_email.body", s)));
+    }
+    for (field, template) in templates.into_iter() {
+        if let Err(e) = ReportTemplate::new(template.as_str()) {
+            diagnostics.push(format!("{}.{}", path, field), e);
+        }
+    }
+
+    if let Some(report_webhook) = config.report_webhook.as_ref() {
+        let report = WebhookReporter::new(&report_webhook.url, report_webhook.body.clone());
+        match report {
+            Ok(report) => {
+                let report = match report.with_headers(&report_webhook.headers) {
+                    Ok(report) => Some(report),
+                    Err(e) => {
+                        diagnostics.push(format!("{}.report_webhook.headers", path), e);
+                        None
+                    }
+                };
+                if let (Some(report), Some(failure_body)) =
+                    (report, report_webhook.failure_body.clone())
+                {
+                    if let Err(e) = report.with_failure_body(failure_body) {
+                        diagnostics.push(format!("{}.report_webhook.failure_body", path), e);
+                    }
+                }
+            }
+            Err(e) => diagnostics.push(format!("{}.report_webhook.body", path), e),
+        }
+    }
+    if let Some(report_email) = config.report_email.as_ref() {
+        if let Err(e) = EmailReporter::new(&report_email.host, &report_email.from, &report_email.to)
+        {
+            diagnostics.push(format!("{}.report_email", path), e);
+        }
+    }
+}
+
+/// 設定ファイル全体の値を検証する．
+fn check_root(
+    root: &Value,
+    names: &mut BTreeSet<String>,
+    check_writable: bool,
+    diagnostics: &mut Diagnostics,
+) {
+    let Some(object) = root.as_object() else {
+        diagnostics.push("", "Config must be an object.");
+        return;
+    };
+    for key in object.keys() {
+        if ![
+            "include",
+            "max_concurrent_jobs",
+            "api_token",
+            "search_and_reports",
+        ]
+        .contains(&key.as_str())
+        {
+            diagnostics.push(key.as_str(), "Unknown field.");
+        }
+    }
+    if let Some(max_concurrent_jobs) = object.get("max_concurrent_jobs") {
+        if let Err(e) = NonZeroUsize::deserialize(max_concurrent_jobs) {
+            diagnostics.push("max_concurrent_jobs", e);
+        }
+    }
+    let Some(jobs) = object.get("search_and_reports") else {
+        return;
+    };
+    let Some(jobs) = jobs.as_array() else {
+        diagnostics.push("search_and_reports", "Expected an array.");
+        return;
+    };
+
+    for (i, job) in jobs.iter().enumerate() {
+        let path = format!("search_and_reports[{}]", i);
+        let config: SearchAndReportConfig = match serde_path_to_error::deserialize(job) {
+            Ok(config) => config,
+            Err(e) => {
+                // フィールドが欠けている場合などはジョブ自体のパスとなる
+                let field_path = e.path().to_string();
+                let field_path = if field_path == "." || field_path.contains('?') {
+                    path
+                } else {
+                    format!("{}.{}", path, field_path)
+                };
+                diagnostics.push(field_path, e.inner());
+                continue;
+            }
+        };
+
+        let mut fields = Vec::new();
+        match serde_json::to_value(&config) {
+            Ok(parsed) => unknown_fields(job, &parsed, &path, &mut fields),
+            Err(e) => diagnostics.push(path.as_str(), e),
+        }
+        for field in fields.into_iter() {
+            diagnostics.push(field, "Unknown field.");
+        }
+
+        check_job(&config, &path, names, check_writable, diagnostics);
+    }
+}
+
+/// ファイルを読み込んで検証し，`include`するファイルのパスを返す．
+/// 検証したファイルは`stack`に積む．既に検証したファイルは再び検証しない．
+fn check_file(
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    files: &mut Vec<PathBuf>,
+    names: &mut BTreeSet<String>,
+    check_writable: bool,
+    diagnostics: &mut Diagnostics,
+) -> Vec<PathBuf> {
+    match path.canonicalize() {
+        Ok(canonical) if stack.contains(&canonical) => {
+            diagnostics.push("", "Circular include.");
+            return Vec::new();
+        }
+        Ok(canonical) if files.contains(&canonical) => return Vec::new(),
+        Ok(canonical) => {
+            files.push(canonical.clone());
+            stack.push(canonical);
+        }
+        Err(e) => {
+            diagnostics.push("", e);
+            return Vec::new();
+        }
+    }
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => {
+            diagnostics.push("", e);
+            return Vec::new();
+        }
+    };
+    let format = match ConfigFormat::from_path(path) {
+        Ok(format) => format,
+        Err(e) => {
+            diagnostics.push("", e.0);
+            return Vec::new();
+        }
+    };
+
+    // 構文エラーの行番号と位置を除いたメッセージ
+    let parsed = match format {
+        ConfigFormat::Json => serde_json::from_str::<Value>(&source)
+            .map(|root| {
+                let mut indexer = LineIndexer::new(&source);
+                indexer.value(String::new());
+                (root, indexer.lines)
+            })
+            .map_err(|e| (Some(e.line()), json_message(&e))),
+        ConfigFormat::Toml => toml::from_str::<Value>(&source)
+            .map(|root| (root, toml_lines(&source)))
+            .map_err(|e| {
+                let line = e.span().map(|span| line_at(&source, span.start));
+                (line, e.message().to_string())
+            }),
+        ConfigFormat::Yaml => serde_yaml::from_str::<Value>(&source)
+            .map(|root| (root, yaml_lines(&source)))
+            .map_err(|e| (e.location().map(|location| location.line()), e.to_string())),
+    };
+    let mut root = match parsed {
+        Ok((root, lines)) => {
+            diagnostics.lines = lines;
+            root
+        }
+        Err((line, message)) => {
+            diagnostics.diagnostics.push(Diagnostic {
+                file: path.to_path_buf(),
+                path: String::new(),
+                line,
+                message,
+            });
+            return Vec::new();
+        }
+    };
+
+    for (field, name) in interpolate_env(&mut root, "") {
+        diagnostics.push(field, format!("Environment variable {} is not set.", name));
+    }
+    check_root(&root, names, check_writable, diagnostics);
+
+    includes(&root, path).unwrap_or_else(|e| {
+        diagnostics.push("include", e.0);
+        Vec::new()
+    })
+}
+
+fn validate_file(
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    files: &mut Vec<PathBuf>,
+    names: &mut BTreeSet<String>,
+    check_writable: bool,
+    output: &mut Vec<Diagnostic>,
+) {
+    let mut diagnostics = Diagnostics {
+        file: path.to_path_buf(),
+        lines: BTreeMap::new(),
+        diagnostics: Vec::new(),
+    };
+    let depth = stack.len();
+    let includes = check_file(path, stack, files, names, check_writable, &mut diagnostics);
+    output.extend(diagnostics.diagnostics);
+
+    for include in includes.iter() {
+        validate_file(include, stack, files, names, check_writable, output);
+    }
+    stack.truncate(depth);
+}
+
+/// 設定ファイルを検証し，全ての問題を返す．`include`したファイルも検証する．
+/// `check_writable`の場合は保存先のディレクトリに確認用のファイルを作製・削除して書き込めるかを確かめる．
+pub fn validate(path: &Path, check_writable: bool) -> Vec<Diagnostic> {
+    let mut files = Vec::new();
+    let mut names = BTreeSet::new();
+    let mut diagnostics = Vec::new();
+    validate_file(
+        path,
+        &mut Vec::new(),
+        &mut files,
+        &mut names,
+        check_writable,
+        &mut diagnostics,
+    );
+    diagnostics
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_dir, validate, Diagnostic};
+    use crate::config::{AllConfig, ConfigFormat};
+
+    #[test]
+    fn test_validate() {
+        let dir_path =
+            std::env::temp_dir().join(format!("search_and_report_validate_{}", std::process::id()));
+        std::fs::create_dir_all(&dir_path).unwrap();
+
+        let config_json = r#"{
+    "include": ["jobs.yaml"],
+    "search_and_reports": [
+        {
+            "name": "rust",
+            "keywords": [],
+            "platform": "YahooJp",
+            "cron": "every day",
+            "conditon_contain": ["CLI"],
+            "report_json_dir": "./Cargo.toml/reports",
+            "report_os_content": "{{ count"
+        },
+        {
+            "name": "rust-twitter",
+            "keywords": ["Rust"],
+            "platform": "Twitter",
+            "cron": "0 0 6 * * * *"
+        }
+    ],
+    "version": 2
+}"#;
+        let jobs_yaml = r#"
+search_and_reports:
+  - name: rust
+    keywords: ["Go"]
+    platform: YahooJp
+    cron: "0 0 6 * * * *"
+    report_os_content: "${SEARCH_AND_REPORT_TEST_UNSET}"
+"#;
+        let config_path = dir_path.join("config.json");
+        std::fs::write(&config_path, config_json).unwrap();
+        std::fs::write(dir_path.join("jobs.yaml"), jobs_yaml).unwrap();
+
+        let diagnostics = validate(&config_path, false)
+            .into_iter()
+            .map(
+                |Diagnostic {
+                     file, path, line, ..
+                 }| { (file.file_name().unwrap().to_owned(), path, line) },
+            )
+            .collect::<Vec<_>>();
+        let expected = [
+            ("config.json", "version", Some(20)),
+            (
+                "config.json",
+                "search_and_reports[0].conditon_contain",
+                Some(9),
+            ),
+            ("config.json", "search_and_reports[0].keywords", Some(6)),
+            ("config.json", "search_and_reports[0].cron", Some(8)),
+            (
+                "config.json",
+                "search_and_reports[0].report_json_dir",
+                Some(10),
+            ),
+            (
+                "config.json",
+                "search_and_reports[0].report_os_content",
+                Some(11),
+            ),
+            ("config.json", "search_and_reports[1].platform", Some(16)),
+            (
+                "jobs.yaml",
+                "search_and_reports[0].report_os_content",
+                Some(7),
+            ),
+            ("jobs.yaml", "search_and_reports[0].name", Some(3)),
+        ]
+        .into_iter()
+        .map(|(file, path, line)| (file.into(), path.to_string(), line))
+        .collect::<Vec<_>>();
+        assert_eq!(diagnostics, expected);
+
+        // TOMLの行番号
+        let config_toml = r#"
+[[search_and_reports]]
+name = "rust"
+keywords = ["Rust"]
+platform = "YahooJp"
+cron = "every day"
+
+[[search_and_reports]]
+name = "go"
+keywords = []
+platform = "YahooJp"
+cron = "0 0 6 * * * *"
+"#;
+        let toml_path = dir_path.join("config.toml");
+        std::fs::write(&toml_path, config_toml).unwrap();
+        let lines = validate(&toml_path, false)
+            .into_iter()
+            .map(|Diagnostic { path, line, .. }| (path, line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                ("search_and_reports[0].cron".to_string(), Some(6)),
+                ("search_and_reports[1].keywords".to_string(), Some(10)),
+            ]
+        );
+
+        // 構文エラー
+        std::fs::write(&config_path, "{\n  \"search_and_reports\": [\n}").unwrap();
+        let diagnostics = validate(&config_path, false);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, Some(3));
+        std::fs::write(&toml_path, "[[search_and_reports]]\nname = \n").unwrap();
+        assert_eq!(validate(&toml_path, false)[0].line, Some(2));
+        let yaml_path = dir_path.join("jobs.yaml");
+        std::fs::write(&yaml_path, "search_and_reports:\n  - name: [rust\n").unwrap();
+        assert_eq!(validate(&yaml_path, false)[0].line, Some(3));
+
+        // 各形式のデフォルトの設定
+        for (name, format) in [
+            ("default.json", ConfigFormat::Json),
+            ("default.toml", ConfigFormat::Toml),
+            ("default.yaml", ConfigFormat::Yaml),
+        ] {
+            let path = dir_path.join(name);
+            let source = format.serialize(&AllConfig::default()).unwrap();
+            std::fs::write(&path, source).unwrap();
+            assert_eq!(validate(&path, false), Vec::new(), "{}", name);
+        }
+
+        // 存在しないディレクトリは作製できる祖先を確かめ，確認用のファイルを残さない
+        let entries = std::fs::read_dir(&dir_path).unwrap().count();
+        for probe in [false, true] {
+            assert_eq!(check_dir(&dir_path.join("reports/daily"), probe), Ok(()));
+            assert_eq!(std::fs::read_dir(&dir_path).unwrap().count(), entries);
+            assert!(check_dir(&dir_path.join("config.json/reports"), probe).is_err());
+        }
+
+        std::fs::remove_dir_all(&dir_path).unwrap();
+    }
+}
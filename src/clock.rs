@@ -0,0 +1,51 @@
+use chrono::{DateTime, Duration, Local, Utc};
+use std::sync::RwLock;
+
+/// 現在時刻を取得するためのトレイト．テストや過去時点での再評価のために差し替えられる．
+pub trait Clock: Send + Sync {
+    /// UTCの現在時刻
+    fn now(&self) -> DateTime<Utc>;
+
+    /// ローカルの現在時刻
+    fn now_local(&self) -> DateTime<Local> {
+        self.now().with_timezone(&Local)
+    }
+}
+
+/// システムの時刻をそのまま返す時計
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// 任意の時刻に固定された時計．`set`や`advance`で時刻を進めることができる．
+#[derive(Debug)]
+pub struct FixedClock {
+    now: RwLock<DateTime<Utc>>,
+}
+
+impl FixedClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: RwLock::new(now),
+        }
+    }
+    /// 時刻を設定する．
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.write().unwrap() = now;
+    }
+    /// 時刻を進める．
+    pub fn advance(&self, duration: Duration) {
+        *self.now.write().unwrap() += duration;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.read().unwrap()
+    }
+}
@@ -7,23 +7,96 @@ pub enum Error {
     /// リクエストして取得したソースが意図しないものであった場合のエラー
     #[error("Error::UnexpectedStructureError: {selector} is not found in source.")]
     UnexpectedStructureError { selector: String },
+    /// CAPTCHAやアクセス制限のページが返された場合のエラー
+    #[error("Error::BlockedPageError: {0}")]
+    BlockedPageError(String),
     /// Datetimeのパースに関するエラー
     #[error("Error::ParseDatetimeError: {0}")]
     ParseDatetimeError(String),
+    /// パースに失敗したポストが許容範囲を超えた場合のエラー
+    #[error("Error::ErrorBudgetExceededError: {failed} of {total} posts failed to parse. First: {first}")]
+    ErrorBudgetExceededError {
+        failed: usize,
+        total: usize,
+        first: String,
+    },
+    /// スケジュールに関するエラー
+    #[error("Error::ScheduleError: {0}")]
+    ScheduleError(String),
+    /// テンプレートに関するエラー
+    #[error("Error::TemplateError: {0}")]
+    TemplateError(String),
     /// リクエストに関するエラー
     #[error("Error::RequestError: {0}")]
     RequestError(String),
+    /// 接続に失敗した場合のエラー
+    #[error("Error::ConnectionError: {0}")]
+    ConnectionError(String),
+    /// 成功以外のステータスコードが返された場合のエラー
+    #[error("Error::HttpStatusError: {url} responded with {status}.")]
+    HttpStatusError {
+        url: String,
+        status: u16,
+        /// Retry-Afterヘッダーで指定された再試行の時期
+        retry_after: Option<crate::request::RetryAfter>,
+    },
     /// ファイルのI/Oに関するエラー
     #[error("Error::FileError: {0}")]
     FileError(String),
+    /// データベースに関するエラー
+    #[error("Error::DatabaseError: {0}")]
+    DatabaseError(String),
+    /// メールの送信に関するエラー
+    #[error("Error::MailError: {0}")]
+    MailError(String),
+    /// 外部コマンドの実行に関するエラー
+    #[error("Error::CommandError: {0}")]
+    CommandError(String),
     /// OSに関するエラー
     #[error("Error::OsError: {0}")]
     OsError(String),
+    /// 処理がタイムアウトした場合のエラー
+    #[error("Error::TimeoutError: {0}")]
+    TimeoutError(String),
+    /// 一部または全てのリポーターが失敗した場合のエラー
+    #[error("Error::ReportError: {} reporters failed. {}", .failures.len(), .failures.join("; "))]
+    ReportError { failures: Vec<String> },
     /// リポートするポストが存在しない場合のエラー
     #[error("Error::NothingPostError: The posts is nothing to report.")]
     NothingPostError,
 }
 
+impl Error {
+    /// ページの構造が想定と異なる(マークアップの変更などが疑われる)エラーかどうか
+    pub fn is_page_structure_error(&self) -> bool {
+        matches!(
+            self,
+            Error::UnexpectedStructureError { .. }
+                | Error::BlockedPageError(_)
+                | Error::ErrorBudgetExceededError { .. }
+        )
+    }
+
+    /// 再試行によって成功する可能性のある一時的なエラーかどうか
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::TimeoutError(_) | Error::ConnectionError(_) => true,
+            Error::HttpStatusError { status, .. } => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// サーバーから指定された再試行までの`now`からの待機時間
+    pub fn retry_after(&self, now: chrono::DateTime<chrono::Utc>) -> Option<std::time::Duration> {
+        match self {
+            Error::HttpStatusError { retry_after, .. } => {
+                retry_after.map(|retry_after| retry_after.delay(now))
+            }
+            _ => None,
+        }
+    }
+}
+
 impl<'token> From<scraper::error::SelectorErrorKind<'token>> for Error {
     fn from(value: scraper::error::SelectorErrorKind<'token>) -> Self {
         Self::ScraperError(value.to_string())
@@ -32,7 +105,19 @@ impl<'token> From<scraper::error::SelectorErrorKind<'token>> for Error {
 
 impl From<reqwest::Error> for Error {
     fn from(value: reqwest::Error) -> Self {
-        Self::RequestError(value.to_string())
+        if value.is_timeout() {
+            Self::TimeoutError(value.to_string())
+        } else if value.is_connect() {
+            Self::ConnectionError(value.to_string())
+        } else {
+            Self::RequestError(value.to_string())
+        }
+    }
+}
+
+impl From<tokio::task::JoinError> for Error {
+    fn from(value: tokio::task::JoinError) -> Self {
+        Self::OsError(value.to_string())
     }
 }
 
@@ -48,6 +133,72 @@ impl From<serde_json::Error> for Error {
     }
 }
 
+impl From<atom_syndication::Error> for Error {
+    fn from(value: atom_syndication::Error) -> Self {
+        Self::FileError(value.to_string())
+    }
+}
+
+impl From<rss::Error> for Error {
+    fn from(value: rss::Error) -> Self {
+        Self::FileError(value.to_string())
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(value: csv::Error) -> Self {
+        Self::FileError(value.to_string())
+    }
+}
+
+impl From<parquet::errors::ParquetError> for Error {
+    fn from(value: parquet::errors::ParquetError) -> Self {
+        Self::FileError(value.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(value: rusqlite::Error) -> Self {
+        Self::DatabaseError(value.to_string())
+    }
+}
+
+impl From<regex::Error> for Error {
+    fn from(value: regex::Error) -> Self {
+        Self::TemplateError(value.to_string())
+    }
+}
+
+impl From<minijinja::Error> for Error {
+    fn from(value: minijinja::Error) -> Self {
+        Self::TemplateError(value.to_string())
+    }
+}
+
+impl From<lettre::address::AddressError> for Error {
+    fn from(value: lettre::address::AddressError) -> Self {
+        Self::MailError(value.to_string())
+    }
+}
+
+impl From<lettre::error::Error> for Error {
+    fn from(value: lettre::error::Error) -> Self {
+        Self::MailError(value.to_string())
+    }
+}
+
+impl From<lettre::transport::smtp::Error> for Error {
+    fn from(value: lettre::transport::smtp::Error) -> Self {
+        if value.is_timeout() {
+            Self::TimeoutError(value.to_string())
+        } else if value.is_transient() {
+            Self::ConnectionError(value.to_string())
+        } else {
+            Self::MailError(value.to_string())
+        }
+    }
+}
+
 impl From<notify_rust::error::Error> for Error {
     fn from(value: notify_rust::error::Error) -> Self {
         Self::OsError(value.to_string())
@@ -1,12 +1,17 @@
+pub mod clock;
 pub mod error;
+pub mod metrics;
 pub mod parser;
 pub mod predicates;
 pub mod reporter;
 pub mod request;
+pub mod retry;
+pub mod schedule;
 
 pub mod platforms;
 
-pub use parser::PostParser;
+pub use clock::Clock;
+pub use parser::{ErrorBudget, PostParser};
 pub use reporter::Report;
 pub use request::RequestSource;
 
@@ -14,8 +19,10 @@ use chrono::{NaiveDate, NaiveTime};
 use serde::{Deserialize, Serialize};
 
 /// ポストを表す型
-#[derive(Clone, PartialEq, Eq, Serialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Default)]
 pub struct Post {
+    /// プラットフォーム内でポストを識別するID
+    pub id: String,
     /// アカウント名
     pub author: String,
     /// ローカルの日時
@@ -26,10 +33,28 @@ pub struct Post {
     pub content: String,
 }
 
+impl Post {
+    /// ポストの内容から求めたID．プラットフォームのIDが取得できない場合に用いる．
+    /// 実行環境によらず同じ値となるようにFNV-1aでハッシュする．
+    /// 日時は「3分前」などの相対表記から求めるため実行時刻によって変わる．アカウント名と内容のみを用いる．
+    pub fn content_hash_id(&self) -> String {
+        let hash = [self.author.as_str(), &self.content]
+            .iter()
+            .flat_map(|field| field.bytes().chain(std::iter::once(0)))
+            .fold(0xcbf29ce484222325_u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            });
+
+        format!("hash:{:016x}", hash)
+    }
+}
+
 pub type Posts = Vec<Post>;
 
 /// プラットフォームやバージョン管理用のためのトレイト
 pub trait PlatForm {
+    /// 保存時などにプラットフォームを識別する名前
+    const NAME: &'static str;
     type Parser: PostParser;
     type Requester: RequestSource;
 }
@@ -37,31 +62,154 @@ pub trait PlatForm {
 /// 検索・リポート設定
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SearchConfig {
+    /// ジョブの名前．ログ・リポートのファイル名・通知のタイトルに用いる．
+    pub name: String,
     pub keywords: Vec<String>,
+    /// パースに失敗したポストの許容範囲
+    #[serde(default)]
+    pub parse_error_budget: ErrorBudget,
+    /// リクエストの再試行の設定
+    #[serde(default)]
+    pub request_retry: retry::RetryPolicy,
 }
 
 impl Default for SearchConfig {
     fn default() -> Self {
         SearchConfig {
+            name: "rust".to_string(),
             keywords: vec!["Rust".to_string()],
+            parse_error_budget: Default::default(),
+            request_retry: Default::default(),
         }
     }
 }
 
+/// 検索・リポートの結果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SearchOutcome {
+    pub posts: Posts,
+    pub verdicts: Vec<predicates::Verdict>,
+}
+
+impl SearchOutcome {
+    /// いずれかの条件に一致したかどうか
+    pub fn matched(&self) -> bool {
+        self.verdicts.iter().any(|verdict| verdict.matched)
+    }
+}
+
+/// 検索してポストをパースする．リポートは行わない．
+pub async fn search<T: PlatForm>(
+    config: &SearchConfig,
+    clock: &dyn Clock,
+) -> Result<parser::ParseOutput, error::Error> {
+    let metrics = metrics::global();
+    let source = config
+        .request_retry
+        .run("Request", clock, || async {
+            let started = std::time::Instant::now();
+            let res = T::Requester::request(&config.keywords).await;
+            metrics.record_request(&config.name, started.elapsed(), res.is_ok());
+            res
+        })
+        .await?;
+
+    let output = T::Parser::parse(source, clock, &config.parse_error_budget).inspect_err(|_| {
+        metrics.record_parse_failure(&config.name);
+    })?;
+    metrics.record_posts(&config.name, output.posts.len(), output.diagnostics.len());
+
+    if output.page_status == parser::PageStatus::NoResults {
+        tracing::info!("No results for {:?}.", config.keywords);
+    }
+
+    if !output.diagnostics.is_empty() {
+        tracing::warn!(
+            "{} posts were skipped while parsing.",
+            output.diagnostics.len()
+        );
+    }
+
+    Ok(output)
+}
+
 /// 検索とリポートを行う公開API
-pub async fn search_and_report<T: PlatForm, R: Report, P: Fn(&Posts) -> bool>(
+pub async fn search_and_report<
+    T: PlatForm,
+    R: Report,
+    P: Fn(&Posts) -> Vec<predicates::Verdict>,
+>(
     config: &SearchConfig,
     _platform: &T,
     reporter: &R,
     pred: P,
-) -> Result<(), error::Error> {
-    let source = T::Requester::request(&config.keywords).await?;
+    clock: &dyn Clock,
+) -> Result<SearchOutcome, error::Error> {
+    let posts = match search::<T>(config, clock).await {
+        Ok(output) => output.posts,
+        Err(e) if e.is_page_structure_error() => {
+            // ページ構造の変化はポストが無い場合と区別して通知する
+            tracing::error!("Page structure is not recognized. {}", e);
+            let context = reporter::ReportContext {
+                job_name: Some(&config.name),
+                platform: T::NAME,
+                keywords: &config.keywords,
+                posts: &Vec::new(),
+                verdicts: &[],
+            };
+            reporter.report_failure(&context, &e).await?;
+            return Err(e);
+        }
+        Err(e) => return Err(e),
+    };
+
+    let verdicts = pred(&posts);
+    metrics::global().record_verdicts(&config.name, &verdicts);
+    let context = reporter::ReportContext {
+        job_name: Some(&config.name),
+        platform: T::NAME,
+        keywords: &config.keywords,
+        posts: &posts,
+        verdicts: &verdicts,
+    };
 
-    let posts = T::Parser::parse(source)?;
+    // 記録の失敗でリポートが妨げられないように，エラーはリポートの後に返す
+    let record_result = reporter.record(&context).await;
 
-    if pred(&posts) {
-        reporter.report(&posts).await?;
+    if verdicts.iter().any(|verdict| verdict.matched) {
+        reporter.report(&context).await?;
     }
 
-    Ok(())
+    record_result.map(|_| SearchOutcome { posts, verdicts })
+}
+
+#[cfg(test)]
+mod test {
+    use super::Post;
+
+    use chrono::{NaiveDate, NaiveTime};
+
+    #[test]
+    fn test_content_hash_id() {
+        let post = Post {
+            author: "author".to_string(),
+            date: NaiveDate::from_ymd_opt(2023, 11, 27).unwrap(),
+            time: NaiveTime::from_hms_opt(12, 0, 0),
+            content: "content".to_string(),
+            ..Default::default()
+        };
+        // 解決した日時が変わっても同じIDとなる
+        let later = Post {
+            date: NaiveDate::from_ymd_opt(2023, 11, 28).unwrap(),
+            time: None,
+            ..post.clone()
+        };
+        assert_eq!(post.content_hash_id(), later.content_hash_id());
+
+        let other = Post {
+            content: "other".to_string(),
+            ..post.clone()
+        };
+        assert_ne!(post.content_hash_id(), other.content_hash_id());
+    }
 }
@@ -0,0 +1,513 @@
+use crate::predicates::Verdict;
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// リクエストの所要時間のヒストグラムの境界(秒)
+const LATENCY_BUCKETS: [f64; 9] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// プロセス全体で共有するメトリクス
+static GLOBAL: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+/// 検索・リポートで記録するメトリクス
+pub fn global() -> &'static Metrics {
+    &GLOBAL
+}
+
+/// 累積のヒストグラム
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// 各境界以下の観測数
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if value <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+/// ジョブごとのメトリクス
+#[derive(Debug, Clone, Default)]
+struct JobMetrics {
+    requests_succeeded: u64,
+    requests_failed: u64,
+    request_duration: Histogram,
+    parse_failures: u64,
+    posts_parsed: u64,
+    posts_skipped: u64,
+    /// 条件の名前ごとの一致の回数
+    matches: BTreeMap<String, u64>,
+    runs_succeeded: u64,
+    runs_failed: u64,
+    last_success: Option<DateTime<Utc>>,
+    /// ジョブを設定した日時．成功するまでの経過時間の基準となる．
+    configured: Option<DateTime<Utc>>,
+}
+
+/// リポーターごとのメトリクス
+#[derive(Debug, Clone, Default)]
+struct ReporterMetrics {
+    sent: u64,
+    failed: u64,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    jobs: BTreeMap<String, JobMetrics>,
+    /// 設定されているジョブの名前．`None`の場合は全てのジョブを記録する．
+    configured_jobs: Option<BTreeSet<String>>,
+    /// ジョブの名前とリポーターの名前ごとのメトリクス
+    reporters: BTreeMap<(String, String), ReporterMetrics>,
+}
+
+impl Inner {
+    /// ジョブを記録するかどうか
+    fn is_configured(&self, job: &str) -> bool {
+        self.configured_jobs
+            .as_ref()
+            .is_none_or(|configured_jobs| configured_jobs.contains(job))
+    }
+}
+
+/// ジョブ・リポーターごとのメトリクス．Prometheusのテキスト形式で出力する．
+#[derive(Debug, Default)]
+pub struct Metrics {
+    inner: Mutex<Inner>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_job<F: FnOnce(&mut JobMetrics)>(&self, job: &str, f: F) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.is_configured(job) {
+            f(inner.jobs.entry(job.to_string()).or_default())
+        }
+    }
+
+    /// 設定されているジョブを登録する．登録したジョブは一度も成功していなくても`now`からの
+    /// 経過時間を出力し，登録されていないジョブのメトリクスは破棄して以降も記録しない．
+    pub fn configure_jobs<'a, I: IntoIterator<Item = &'a str>>(&self, jobs: I, now: DateTime<Utc>) {
+        let mut inner = self.inner.lock().unwrap();
+        let configured_jobs = jobs
+            .into_iter()
+            .map(str::to_string)
+            .collect::<BTreeSet<_>>();
+        inner.jobs.retain(|job, _| configured_jobs.contains(job));
+        inner
+            .reporters
+            .retain(|(job, _), _| configured_jobs.contains(job));
+        for job in configured_jobs.iter() {
+            inner
+                .jobs
+                .entry(job.clone())
+                .or_default()
+                .configured
+                .get_or_insert(now);
+        }
+        inner.configured_jobs = Some(configured_jobs);
+    }
+
+    /// 一回のリクエストの結果と所要時間を記録する．
+    pub fn record_request(&self, job: &str, duration: Duration, succeeded: bool) {
+        self.with_job(job, |metrics| {
+            if succeeded {
+                metrics.requests_succeeded += 1;
+            } else {
+                metrics.requests_failed += 1;
+            }
+            metrics.request_duration.observe(duration.as_secs_f64());
+        })
+    }
+
+    /// ページのパースに失敗したことを記録する．
+    pub fn record_parse_failure(&self, job: &str) {
+        self.with_job(job, |metrics| metrics.parse_failures += 1)
+    }
+
+    /// パースしたポストと読み飛ばしたポストの数を記録する．
+    pub fn record_posts(&self, job: &str, parsed: usize, skipped: usize) {
+        self.with_job(job, |metrics| {
+            metrics.posts_parsed += parsed as u64;
+            metrics.posts_skipped += skipped as u64;
+        })
+    }
+
+    /// 一致した条件を記録する．
+    pub fn record_verdicts(&self, job: &str, verdicts: &[Verdict]) {
+        self.with_job(job, |metrics| {
+            for verdict in verdicts.iter().filter(|verdict| verdict.matched) {
+                *metrics.matches.entry(verdict.name.clone()).or_default() += 1;
+            }
+        })
+    }
+
+    /// 検索・リポートの実行の結果を記録する．`finished`は成功した場合の最終成功日時となる．
+    pub fn record_run(&self, job: &str, succeeded: bool, finished: DateTime<Utc>) {
+        self.with_job(job, |metrics| {
+            if succeeded {
+                metrics.runs_succeeded += 1;
+                metrics.last_success = Some(finished);
+            } else {
+                metrics.runs_failed += 1;
+            }
+        })
+    }
+
+    /// ジョブのリポーターによるリポートの送信の結果を記録する．
+    pub fn record_report(&self, job: &str, reporter: &str, succeeded: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.is_configured(job) {
+            return;
+        }
+        let metrics = inner
+            .reporters
+            .entry((job.to_string(), reporter.to_string()))
+            .or_default();
+        if succeeded {
+            metrics.sent += 1;
+        } else {
+            metrics.failed += 1;
+        }
+    }
+
+    /// Prometheusのテキスト形式で出力する．最終成功からの経過時間は`now`を基準とする．
+    /// 登録したジョブが一度も成功していない場合，最終成功日時は0，経過時間は登録からの時間とする．
+    pub fn render(&self, now: DateTime<Utc>) -> String {
+        let inner = self.inner.lock().unwrap();
+        let jobs = &inner.jobs;
+        let mut out = String::new();
+
+        family(
+            &mut out,
+            "requests_total",
+            "counter",
+            "Search requests including retries.",
+            jobs.iter().flat_map(|(job, metrics)| {
+                [
+                    (
+                        labels(&[("job", job), ("result", "success")]),
+                        metrics.requests_succeeded as f64,
+                    ),
+                    (
+                        labels(&[("job", job), ("result", "failure")]),
+                        metrics.requests_failed as f64,
+                    ),
+                ]
+            }),
+        );
+
+        header(
+            &mut out,
+            "request_duration_seconds",
+            "histogram",
+            "Duration of search requests.",
+        );
+        for (job, metrics) in jobs {
+            let histogram = &metrics.request_duration;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                sample(
+                    &mut out,
+                    "request_duration_seconds_bucket",
+                    &labels(&[("job", job), ("le", &bound.to_string())]),
+                    count as f64,
+                );
+            }
+            sample(
+                &mut out,
+                "request_duration_seconds_bucket",
+                &labels(&[("job", job), ("le", "+Inf")]),
+                histogram.count as f64,
+            );
+            sample(
+                &mut out,
+                "request_duration_seconds_sum",
+                &labels(&[("job", job)]),
+                histogram.sum,
+            );
+            sample(
+                &mut out,
+                "request_duration_seconds_count",
+                &labels(&[("job", job)]),
+                histogram.count as f64,
+            );
+        }
+
+        family(
+            &mut out,
+            "parse_failures_total",
+            "counter",
+            "Search result pages that failed to parse.",
+            jobs.iter()
+                .map(|(job, metrics)| (labels(&[("job", job)]), metrics.parse_failures as f64)),
+        );
+        family(
+            &mut out,
+            "posts_parsed_total",
+            "counter",
+            "Posts parsed from search results.",
+            jobs.iter()
+                .map(|(job, metrics)| (labels(&[("job", job)]), metrics.posts_parsed as f64)),
+        );
+        family(
+            &mut out,
+            "posts_skipped_total",
+            "counter",
+            "Posts skipped because they failed to parse.",
+            jobs.iter()
+                .map(|(job, metrics)| (labels(&[("job", job)]), metrics.posts_skipped as f64)),
+        );
+        family(
+            &mut out,
+            "predicate_matches_total",
+            "counter",
+            "Searches matching each predicate.",
+            jobs.iter().flat_map(|(job, metrics)| {
+                metrics.matches.iter().map(move |(predicate, count)| {
+                    (
+                        labels(&[("job", job), ("predicate", predicate)]),
+                        *count as f64,
+                    )
+                })
+            }),
+        );
+        family(
+            &mut out,
+            "runs_total",
+            "counter",
+            "Scheduled or triggered runs.",
+            jobs.iter().flat_map(|(job, metrics)| {
+                [
+                    (
+                        labels(&[("job", job), ("result", "success")]),
+                        metrics.runs_succeeded as f64,
+                    ),
+                    (
+                        labels(&[("job", job), ("result", "failure")]),
+                        metrics.runs_failed as f64,
+                    ),
+                ]
+            }),
+        );
+        family(
+            &mut out,
+            "last_success_timestamp_seconds",
+            "gauge",
+            "Unix time of the last successful run.",
+            jobs.iter().filter_map(|(job, metrics)| {
+                match (metrics.last_success, metrics.configured) {
+                    (Some(last), _) => Some(last.timestamp() as f64),
+                    (None, Some(_)) => Some(0.0),
+                    (None, None) => None,
+                }
+                .map(|timestamp| (labels(&[("job", job)]), timestamp))
+            }),
+        );
+        family(
+            &mut out,
+            "seconds_since_last_success",
+            "gauge",
+            "Seconds since the last successful run.",
+            jobs.iter().filter_map(|(job, metrics)| {
+                metrics.last_success.or(metrics.configured).map(|last| {
+                    (
+                        labels(&[("job", job)]),
+                        (now - last).num_milliseconds() as f64 / 1000.0,
+                    )
+                })
+            }),
+        );
+
+        let reporters = &inner.reporters;
+        family(
+            &mut out,
+            "reports_sent_total",
+            "counter",
+            "Reports sent successfully.",
+            reporters.iter().map(|((job, reporter), metrics)| {
+                (
+                    labels(&[("job", job), ("reporter", reporter)]),
+                    metrics.sent as f64,
+                )
+            }),
+        );
+        family(
+            &mut out,
+            "reports_failed_total",
+            "counter",
+            "Reports that failed after retries.",
+            reporters.iter().map(|((job, reporter), metrics)| {
+                (
+                    labels(&[("job", job), ("reporter", reporter)]),
+                    metrics.failed as f64,
+                )
+            }),
+        );
+
+        out
+    }
+}
+
+/// メトリクスの名前の接頭辞
+const PREFIX: &str = "search_and_report_";
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {}{} {}", PREFIX, name, help);
+    let _ = writeln!(out, "# TYPE {}{} {}", PREFIX, name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &str, value: f64) {
+    let _ = writeln!(out, "{}{}{{{}}} {}", PREFIX, name, labels, value);
+}
+
+/// 一種類のメトリクスを出力する．
+fn family<I: IntoIterator<Item = (String, f64)>>(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    samples: I,
+) {
+    header(out, name, kind, help);
+    for (labels, value) in samples {
+        sample(out, name, &labels, value);
+    }
+}
+
+/// ラベルを`name="value"`の形式で連結する．値の`\`・`"`・改行はエスケープする．
+fn labels(pairs: &[(&str, &str)]) -> String {
+    pairs
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", name, value)
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod test {
+    use super::Metrics;
+    use crate::predicates::Verdict;
+
+    use chrono::{Duration, TimeZone, Utc};
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        let now = Utc.with_ymd_and_hms(2023, 11, 27, 0, 0, 0).unwrap();
+
+        metrics.record_request("rust", std::time::Duration::from_millis(300), false);
+        metrics.record_request("rust", std::time::Duration::from_secs(2), true);
+        metrics.record_posts("rust", 20, 1);
+        metrics.record_verdicts(
+            "rust",
+            &[
+                Verdict {
+                    name: "count".to_string(),
+                    matched: true,
+                },
+                Verdict {
+                    name: "latest".to_string(),
+                    matched: false,
+                },
+            ],
+        );
+        metrics.record_run("rust", true, now - Duration::seconds(90));
+        metrics.record_parse_failure("go \"cli\"");
+        metrics.record_run("go \"cli\"", false, now);
+        metrics.record_report("rust", "WebhookReporter", true);
+        metrics.record_report("go \"cli\"", "WebhookReporter", false);
+        metrics.record_report("rust", "EmailReporter", false);
+
+        let text = metrics.render(now);
+        for line in [
+            "# TYPE search_and_report_requests_total counter",
+            "search_and_report_requests_total{job=\"rust\",result=\"success\"} 1",
+            "search_and_report_requests_total{job=\"rust\",result=\"failure\"} 1",
+            "# TYPE search_and_report_request_duration_seconds histogram",
+            "search_and_report_request_duration_seconds_bucket{job=\"rust\",le=\"0.25\"} 0",
+            "search_and_report_request_duration_seconds_bucket{job=\"rust\",le=\"0.5\"} 1",
+            "search_and_report_request_duration_seconds_bucket{job=\"rust\",le=\"2.5\"} 2",
+            "search_and_report_request_duration_seconds_bucket{job=\"rust\",le=\"+Inf\"} 2",
+            "search_and_report_request_duration_seconds_sum{job=\"rust\"} 2.3",
+            "search_and_report_request_duration_seconds_count{job=\"rust\"} 2",
+            "search_and_report_parse_failures_total{job=\"go \\\"cli\\\"\"} 1",
+            "search_and_report_posts_parsed_total{job=\"rust\"} 20",
+            "search_and_report_posts_skipped_total{job=\"rust\"} 1",
+            "search_and_report_predicate_matches_total{job=\"rust\",predicate=\"count\"} 1",
+            "search_and_report_runs_total{job=\"go \\\"cli\\\"\",result=\"failure\"} 1",
+            "search_and_report_last_success_timestamp_seconds{job=\"rust\"} 1701043110",
+            "search_and_report_seconds_since_last_success{job=\"rust\"} 90",
+            "search_and_report_reports_sent_total{job=\"rust\",reporter=\"WebhookReporter\"} 1",
+            "search_and_report_reports_failed_total{job=\"go \\\"cli\\\"\",reporter=\"WebhookReporter\"} 1",
+            "search_and_report_reports_failed_total{job=\"rust\",reporter=\"EmailReporter\"} 1",
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "{} is not in\n{}",
+                line,
+                text
+            );
+        }
+        // 条件に一致しなかった場合・成功していない場合は出力しない
+        assert!(!text.contains("predicate=\"latest\""));
+        assert!(!text.contains("seconds_since_last_success{job=\"go"));
+    }
+
+    #[test]
+    fn test_configure_jobs() {
+        let metrics = Metrics::new();
+        let now = Utc.with_ymd_and_hms(2023, 11, 27, 0, 0, 0).unwrap();
+
+        metrics.record_run("rust", true, now - Duration::seconds(90));
+        metrics.record_report("rust", "WebhookReporter", true);
+        metrics.configure_jobs(["rust", "go"], now - Duration::seconds(60));
+        let text = metrics.render(now);
+        for line in [
+            "search_and_report_seconds_since_last_success{job=\"rust\"} 90",
+            // 成功していないジョブは登録からの経過時間を出力する
+            "search_and_report_last_success_timestamp_seconds{job=\"go\"} 0",
+            "search_and_report_seconds_since_last_success{job=\"go\"} 60",
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "{} is not in\n{}",
+                line,
+                text
+            );
+        }
+
+        // 再読み込みで削除したジョブは出力せず，実行中だった検索の結果も記録しない
+        metrics.configure_jobs(["go", "python"], now);
+        metrics.record_run("rust", true, now);
+        metrics.record_report("rust", "WebhookReporter", true);
+        let text = metrics.render(now);
+        assert!(!text.contains("job=\"rust\""));
+        assert!(text
+            .lines()
+            .any(|l| l == "search_and_report_seconds_since_last_success{job=\"go\"} 60"));
+        assert!(text
+            .lines()
+            .any(|l| l == "search_and_report_seconds_since_last_success{job=\"python\"} 0"));
+    }
+}
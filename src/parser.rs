@@ -1,8 +1,163 @@
 use crate::error::Error;
-use crate::Posts;
+use crate::{Clock, Posts};
+
+use serde::{Deserialize, Serialize};
+
+/// ポストごとのパースの問題
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum ParseIssue {
+    /// セレクタに一致する要素が存在しない
+    MissingSelector { selector: String },
+    /// 日時の文字列がパースできない
+    InvalidDatetime {
+        datetime_str: String,
+        message: String,
+    },
+}
+
+impl std::fmt::Display for ParseIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseIssue::MissingSelector { selector } => {
+                write!(f, "{} is not found in post.", selector)
+            }
+            ParseIssue::InvalidDatetime {
+                datetime_str,
+                message,
+            } => write!(f, "cannot parse {:?}: {}", datetime_str, message),
+        }
+    }
+}
+
+/// パースに失敗したポストの診断情報
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PostDiagnostic {
+    /// ソース中のポストの位置
+    pub index: usize,
+    /// 問題の内容
+    pub issue: ParseIssue,
+    /// 該当するソースの一部
+    pub snippet: String,
+}
+
+impl PostDiagnostic {
+    /// スニペットの最大文字数
+    pub const SNIPPET_MAX_CHARS: usize = 200;
+
+    pub fn new(index: usize, issue: ParseIssue, raw: &str) -> Self {
+        Self {
+            index,
+            issue,
+            snippet: raw.chars().take(Self::SNIPPET_MAX_CHARS).collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for PostDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "post[{}]: {} ({})", self.index, self.issue, self.snippet)
+    }
+}
+
+/// パースしたページの状態．構造が認識できないページはエラーとして扱う．
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum PageStatus {
+    /// 検索結果のポストが存在する
+    Results,
+    /// 検索結果が0件であることが確認できた
+    #[default]
+    NoResults,
+}
+
+/// パース結果．パースできたポストと失敗したポストの診断情報を持つ．
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseOutput {
+    pub page_status: PageStatus,
+    pub posts: Posts,
+    pub diagnostics: Vec<PostDiagnostic>,
+}
+
+impl ParseOutput {
+    /// ソース中に見つかったポストの総数
+    pub fn total(&self) -> usize {
+        self.posts.len() + self.diagnostics.len()
+    }
+}
+
+/// パースに失敗したポストをどれだけ許容するか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorBudget {
+    /// 失敗をいくつでも許容する
+    Unlimited,
+    /// 失敗したポストの個数の上限
+    MaxCount(usize),
+    /// 失敗したポストの割合の上限(%)
+    MaxPercent(u8),
+}
+
+/// 半数までの失敗を許容する．
+impl Default for ErrorBudget {
+    fn default() -> Self {
+        ErrorBudget::MaxPercent(50)
+    }
+}
+
+impl ErrorBudget {
+    /// 予算内かどうか
+    pub fn allows(&self, failed: usize, total: usize) -> bool {
+        match self {
+            ErrorBudget::Unlimited => true,
+            ErrorBudget::MaxCount(max_count) => failed <= *max_count,
+            ErrorBudget::MaxPercent(max_percent) => failed * 100 <= *max_percent as usize * total,
+        }
+    }
+}
 
 /// 各プラットフォームごとにPostをパースするためのトレイト．
 pub trait PostParser {
-    /// パースしてPostsを取得する．
-    fn parse(source: String) -> Result<Posts, Error>;
+    /// パースできたポストと失敗したポストの診断情報を取得する．相対的な時刻は`clock`の現在時刻を基準に解決する．
+    fn parse_partial(source: String, clock: &dyn Clock) -> Result<ParseOutput, Error>;
+
+    /// パースしてポストと診断情報を取得する．失敗したポストが`budget`を超えた場合はエラーとなる．
+    fn parse(
+        source: String,
+        clock: &dyn Clock,
+        budget: &ErrorBudget,
+    ) -> Result<ParseOutput, Error> {
+        let output = Self::parse_partial(source, clock)?;
+
+        if budget.allows(output.diagnostics.len(), output.total()) {
+            Ok(output)
+        } else {
+            Err(Error::ErrorBudgetExceededError {
+                failed: output.diagnostics.len(),
+                total: output.total(),
+                first: output
+                    .diagnostics
+                    .first()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ErrorBudget;
+
+    #[test]
+    fn test_error_budget() {
+        assert!(ErrorBudget::Unlimited.allows(10, 10));
+
+        assert!(ErrorBudget::MaxCount(0).allows(0, 10));
+        assert!(!ErrorBudget::MaxCount(0).allows(1, 10));
+        assert!(ErrorBudget::MaxCount(2).allows(2, 10));
+
+        assert!(ErrorBudget::MaxPercent(50).allows(0, 0));
+        assert!(ErrorBudget::MaxPercent(50).allows(5, 10));
+        assert!(!ErrorBudget::MaxPercent(50).allows(6, 10));
+        assert!(!ErrorBudget::MaxPercent(0).allows(1, 1));
+    }
 }
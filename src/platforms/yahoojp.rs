@@ -8,6 +8,7 @@ use crate::PlatForm;
 pub struct YahooJp;
 
 impl PlatForm for YahooJp {
+    const NAME: &'static str = "yahoojp";
     type Parser = yahoojp_parser::YahooJpParser;
     type Requester = yahoojp_request::YahooJpRequest;
 }
@@ -1,31 +1,81 @@
 use crate::error::Error;
-use crate::PostParser;
-use crate::{Post, Posts};
+use crate::parser::{PageStatus, ParseIssue, ParseOutput, PostDiagnostic};
+use crate::{Clock, Post, PostParser};
 
-use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
 use chrono_tz::Asia::Tokyo;
 
 use once_cell::sync::Lazy;
 use regex::Regex;
-use scraper::{Html, Selector};
-use tracing::{debug, info};
+use scraper::{ElementRef, Html, Selector};
+use tracing::{debug, info, warn};
 
 static DATETIME_PAT_1: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d{1,2})秒前").unwrap());
 static DATETIME_PAT_2: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d{1,2})分前").unwrap());
 static DATETIME_PAT_3: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d{1,2}):(\d{1,2})").unwrap());
 static DATETIME_PAT_4: Lazy<Regex> = Lazy::new(|| Regex::new(r"昨日(\d{1,2}):(\d{1,2})").unwrap());
 static DATETIME_PAT_5: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(\d{1,2})月(\d{1,2})日\([月,火,水,木,金,土,日]\)(\d{1,2}):(\d{1,2})").unwrap()
+    Regex::new(r"(\d{1,2})月(\d{1,2})日\(([月火水木金土日])\)(\d{1,2}):(\d{1,2})").unwrap()
 });
 static DATETIME_PAT_6: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"(\d{4})年(\d{1,2})月(\d{1,2})日").unwrap());
+static STATUS_ID_PAT: Lazy<Regex> = Lazy::new(|| Regex::new(r"/status/(\d+)").unwrap());
 
 /// 東京の現在時刻を取得
-fn now_jp() -> NaiveDateTime {
-    let datetime_jp = chrono::Utc::now().with_timezone(&Tokyo);
+fn now_jp(clock: &dyn Clock) -> NaiveDateTime {
+    let datetime_jp = clock.now().with_timezone(&Tokyo);
     NaiveDateTime::new(datetime_jp.date_naive(), datetime_jp.time())
 }
 
+/// 曜日を表す文字を変換
+fn parse_weekday_jp(weekday_str: &str) -> Result<Weekday, Error> {
+    match weekday_str {
+        "月" => Ok(Weekday::Mon),
+        "火" => Ok(Weekday::Tue),
+        "水" => Ok(Weekday::Wed),
+        "木" => Ok(Weekday::Thu),
+        "金" => Ok(Weekday::Fri),
+        "土" => Ok(Weekday::Sat),
+        "日" => Ok(Weekday::Sun),
+        _ => Err(Error::ParseDatetimeError(format!(
+            "Unexpected weekday: {}",
+            weekday_str
+        ))),
+    }
+}
+
+/// 年の無い月日から東京時間の日時を解決する．前年から翌年までの候補のうち，曜日が一致し
+/// 現在時刻より未来でない最も近いものを選ぶ．該当しない場合はエラーとなる．
+fn resolve_year_jp(
+    month: u32,
+    day: u32,
+    weekday: Weekday,
+    time_jp: NaiveTime,
+    now_jp: NaiveDateTime,
+) -> Result<NaiveDateTime, Error> {
+    let candidates = (now_jp.year() - 1..=now_jp.year() + 1)
+        .rev()
+        .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+        .filter(|date| date.weekday() == weekday)
+        .map(|date| NaiveDateTime::new(date, time_jp))
+        .collect::<Vec<_>>();
+
+    if candidates.is_empty() {
+        return Err(Error::ParseDatetimeError(format!(
+            "No date matches {}/{} ({}) around {}",
+            month, day, weekday, now_jp
+        )));
+    }
+
+    candidates
+        .into_iter()
+        .find(|datetime_jp| *datetime_jp <= now_jp)
+        .ok_or(Error::ParseDatetimeError(format!(
+            "Future date: {}/{} ({}) at {}",
+            month, day, weekday, now_jp
+        )))
+}
+
 /// 時間のパーサー．東京時間からローカルに変換する必要がある．
 fn yahoojp_time_parser(
     datetime_str: &str,
@@ -112,28 +162,24 @@ fn yahoojp_time_parser(
     } else if let Some(captures) = DATETIME_PAT_5.captures(&trimmed) {
         debug!("PAT_5, captures: {:?}", captures);
 
-        let date_jp = {
-            let this_year = now_jp.year();
-            let month = captures[1]
-                .parse::<u32>()
-                .map_err(|e| Error::ParseDatetimeError(e.to_string()))?;
-            let day = captures[2]
-                .parse::<u32>()
-                .map_err(|e| Error::ParseDatetimeError(e.to_string()))?;
-            NaiveDate::from_ymd_opt(this_year, month, day)
-                .ok_or(Error::ParseDatetimeError("unexpected date".to_string()))?
-        };
+        let month = captures[1]
+            .parse::<u32>()
+            .map_err(|e| Error::ParseDatetimeError(e.to_string()))?;
+        let day = captures[2]
+            .parse::<u32>()
+            .map_err(|e| Error::ParseDatetimeError(e.to_string()))?;
+        let weekday = parse_weekday_jp(&captures[3])?;
         let time_jp = {
-            let hour = captures[3]
+            let hour = captures[4]
                 .parse::<u32>()
                 .map_err(|e| Error::ParseDatetimeError(e.to_string()))?;
-            let min = captures[4]
+            let min = captures[5]
                 .parse::<u32>()
                 .map_err(|e| Error::ParseDatetimeError(e.to_string()))?;
             NaiveTime::from_hms_opt(hour, min, 0)
                 .ok_or(Error::ParseDatetimeError("unexpected time".to_string()))?
         };
-        let datetime_jp = NaiveDateTime::new(date_jp, time_jp)
+        let datetime_jp = resolve_year_jp(month, day, weekday, time_jp, now_jp)?
             .and_local_timezone(Tokyo)
             .unwrap();
         let datetime_local = datetime_jp.with_timezone(&Local);
@@ -154,6 +200,12 @@ fn yahoojp_time_parser(
             NaiveDate::from_ymd_opt(year, month, day)
                 .ok_or(Error::ParseDatetimeError("unexpected date".to_string()))?
         };
+        if date > now_jp.date() {
+            return Err(Error::ParseDatetimeError(format!(
+                "Future date: {} at {}",
+                date, now_jp
+            )));
+        }
         // ローカルも一緒としかできない．
         Ok((date, None))
     } else {
@@ -164,99 +216,208 @@ fn yahoojp_time_parser(
     }
 }
 
+/// 検索結果が0件の場合のメッセージ
+const NO_HIT_MESSAGES: &[&str] = &[
+    "に一致する検索結果はありませんでした",
+    "に一致するポストはありません",
+    "に一致するツイートはありません",
+];
+
+/// CAPTCHAやアクセス制限のページに含まれる文字列
+const BLOCKED_MARKERS: &[&str] = &["captcha", "ロボットではありません", "アクセスが制限"];
+
+/// ポストが見つからなかった場合に，0件の検索結果か認識できないページかを判定する．
+fn check_empty_page(document: &Html) -> Result<PageStatus, Error> {
+    let text = document.root_element().text().collect::<String>();
+
+    if NO_HIT_MESSAGES.iter().any(|message| text.contains(message)) {
+        return Ok(PageStatus::NoResults);
+    }
+
+    let html_lower = document.root_element().html().to_lowercase();
+    if let Some(marker) = BLOCKED_MARKERS
+        .iter()
+        .find(|marker| html_lower.contains(&marker.to_lowercase()))
+    {
+        return Err(Error::BlockedPageError(format!(
+            "The page seems to be blocked ({}).",
+            marker
+        )));
+    }
+
+    Err(Error::UnexpectedStructureError {
+        selector: "div[class^=Tweet_bodyContainer]".to_string(),
+    })
+}
+
+/// ポスト内の要素のセレクタ
+struct PostSelectors {
+    author_name: Selector,
+    content: Selector,
+    datetime: Selector,
+}
+
+/// 一つのポストをパースする．
+fn parse_post(
+    body_container: ElementRef,
+    selectors: &PostSelectors,
+    now_jp: NaiveDateTime,
+) -> Result<Post, ParseIssue> {
+    let author_name = body_container.select(&selectors.author_name).next().ok_or(
+        ParseIssue::MissingSelector {
+            selector: "div[class^=Tweet_bodyContainer] span[class^=Tweet_authorName]".to_string(),
+        },
+    )?;
+    let datetime =
+        body_container
+            .select(&selectors.datetime)
+            .next()
+            .ok_or(ParseIssue::MissingSelector {
+                selector: "div[class^=Tweet_bodyContainer] time[class^=Tweet_time] > a".to_string(),
+            })?;
+    let content =
+        body_container
+            .select(&selectors.content)
+            .next()
+            .ok_or(ParseIssue::MissingSelector {
+                selector: "div[class^=Tweet_bodyContainer] div[class^=Tweet_body]".to_string(),
+            })?;
+
+    let mut content_buffer = String::new();
+
+    for content_text in content.text() {
+        content_buffer.push_str(content_text);
+    }
+
+    let datetime_str = datetime.inner_html();
+    let (date, time) =
+        yahoojp_time_parser(&datetime_str, now_jp).map_err(|e| ParseIssue::InvalidDatetime {
+            datetime_str,
+            message: e.to_string(),
+        })?;
+
+    let mut post = Post {
+        id: String::new(),
+        author: author_name.inner_html(),
+        date,
+        time,
+        content: content_buffer,
+    };
+    // 日時のリンク先がポストのURLとなっている
+    post.id = match datetime
+        .value()
+        .attr("href")
+        .and_then(|href| STATUS_ID_PAT.captures(href))
+    {
+        Some(caps) => caps[1].to_string(),
+        None => post.content_hash_id(),
+    };
+
+    Ok(post)
+}
+
 /// Yahoojpに対応したパーサー
 pub struct YahooJpParser;
 
 impl PostParser for YahooJpParser {
-    fn parse(source: String) -> Result<Posts, Error> {
+    fn parse_partial(source: String, clock: &dyn Clock) -> Result<ParseOutput, Error> {
         info!("Parsing html source");
         let document = Html::parse_document(&source);
-        let now_jp = now_jp();
+        let now_jp = now_jp(clock);
 
         let body_container_selector = Selector::parse(r#"div[class^=Tweet_bodyContainer]"#)?;
-        let author_name_selector = Selector::parse(r#"span[class^=Tweet_authorName]"#)?;
-        let content_selector = Selector::parse(r#"div[class^=Tweet_body]"#)?;
-        let datetime_selector = Selector::parse(r#"time[class^=Tweet_time] > a"#)?;
-
-        let mut posts: Vec<Post> = Vec::new();
-
-        for body_container in document.select(&body_container_selector) {
-            let author_name = body_container.select(&author_name_selector).next().ok_or(
-                Error::UnexpectedStructureError {
-                    selector: "div[class^=Tweet_bodyContainer] span[class^=Tweet_authorName]"
-                        .to_string(),
-                },
-            )?;
-            let datetime = body_container.select(&datetime_selector).next().ok_or(
-                Error::UnexpectedStructureError {
-                    selector: "div[class^=Tweet_bodyContainer] time[class^=Tweet_time] > a"
-                        .to_string(),
-                },
-            )?;
-            let content = body_container.select(&content_selector).next().ok_or(
-                Error::UnexpectedStructureError {
-                    selector: "div[class^=Tweet_bodyContainer] div[class^=Tweet_body]".to_string(),
-                },
-            )?;
-
-            let mut content_buffer = String::new();
-
-            for content_text in content.text() {
-                content_buffer.push_str(content_text);
-            }
+        let selectors = PostSelectors {
+            author_name: Selector::parse(r#"span[class^=Tweet_authorName]"#)?,
+            content: Selector::parse(r#"div[class^=Tweet_body]"#)?,
+            datetime: Selector::parse(r#"time[class^=Tweet_time] > a"#)?,
+        };
 
-            let (date, time) = yahoojp_time_parser(&datetime.inner_html(), now_jp)?;
+        let mut output = ParseOutput {
+            page_status: PageStatus::Results,
+            ..Default::default()
+        };
+
+        if document.select(&body_container_selector).next().is_none() {
+            output.page_status = check_empty_page(&document)?;
+            info!("No posts found in source html.");
+            return Ok(output);
+        }
 
-            posts.push(Post {
-                author: author_name.inner_html(),
-                date,
-                time,
-                content: content_buffer,
-            })
+        for (index, body_container) in document.select(&body_container_selector).enumerate() {
+            match parse_post(body_container, &selectors, now_jp) {
+                Ok(post) => output.posts.push(post),
+                Err(issue) => {
+                    let diagnostic = PostDiagnostic::new(index, issue, &body_container.html());
+                    warn!("Skipped post. {}", diagnostic);
+                    output.diagnostics.push(diagnostic);
+                }
+            }
         }
-        info!("Finished parsing source html.");
+        info!(
+            "Finished parsing source html. {} posts, {} failed.",
+            output.posts.len(),
+            output.diagnostics.len()
+        );
 
-        Ok(posts)
+        Ok(output)
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{now_jp, yahoojp_time_parser};
+    use super::{now_jp, yahoojp_time_parser, YahooJpParser};
+    use crate::clock::FixedClock;
+    use crate::error::Error;
+    use crate::parser::{ErrorBudget, PageStatus, ParseIssue, PostParser};
+
+    use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+    use chrono_tz::Asia::Tokyo;
     use tracing_test::traced_test;
 
+    /// 東京時間の日時をローカルの日付・時間に変換する．
+    fn jp_to_local(date_jp: NaiveDate, time_jp: NaiveTime) -> (NaiveDate, Option<NaiveTime>) {
+        let datetime_local = NaiveDateTime::new(date_jp, time_jp)
+            .and_local_timezone(Tokyo)
+            .unwrap()
+            .with_timezone(&Local);
+        (datetime_local.date_naive(), Some(datetime_local.time()))
+    }
+
     #[traced_test]
     #[test]
     fn test_time_parser() {
-        use chrono::{Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
-
-        let now_jp = now_jp();
-        let now_local = {
-            let local = now_jp.and_local_timezone(Local).unwrap();
-            NaiveDateTime::new(local.date_naive(), local.time())
-        };
+        use chrono::Duration;
+
+        // 2023年11月27日(月)12:00(東京時間)
+        let clock = FixedClock::new(
+            Tokyo
+                .with_ymd_and_hms(2023, 11, 27, 12, 0, 0)
+                .unwrap()
+                .to_utc(),
+        );
+        let now_jp = now_jp(&clock);
 
         {
-            let datetime_local = now_local - Duration::seconds(25);
+            let datetime_jp = now_jp - Duration::seconds(25);
             assert_eq!(
                 yahoojp_time_parser("25秒前", now_jp).unwrap(),
-                (datetime_local.date(), Some(datetime_local.time()))
+                jp_to_local(datetime_jp.date(), datetime_jp.time())
             );
         }
 
         {
-            let datetime_local = now_local - Duration::minutes(5);
+            let datetime_jp = now_jp - Duration::minutes(5);
             assert_eq!(
                 yahoojp_time_parser("5分前", now_jp).unwrap(),
-                (datetime_local.date(), Some(datetime_local.time()))
+                jp_to_local(datetime_jp.date(), datetime_jp.time())
             );
         }
 
         assert_eq!(
             yahoojp_time_parser("0:17", now_jp).unwrap(),
-            (
-                now_local.date(),
-                Some(NaiveTime::from_hms_opt(0, 17, 0).unwrap())
+            jp_to_local(
+                NaiveDate::from_ymd_opt(2023, 11, 27).unwrap(),
+                NaiveTime::from_hms_opt(0, 17, 0).unwrap()
             )
         );
 
@@ -266,9 +427,9 @@ mod test {
                 now_jp
             )
             .unwrap(),
-            (
-                now_local.date() - chrono::Duration::days(1),
-                Some(NaiveTime::from_hms_opt(17, 12, 0).unwrap())
+            jp_to_local(
+                NaiveDate::from_ymd_opt(2023, 11, 26).unwrap(),
+                NaiveTime::from_hms_opt(17, 12, 0).unwrap()
             )
         );
 
@@ -276,7 +437,7 @@ mod test {
             yahoojp_time_parser("11月24日(金)\n                                                                3:00",
                 now_jp
             ).unwrap(),
-            (NaiveDate::from_ymd_opt(2023, 11, 24).unwrap(), Some(NaiveTime::from_hms_opt(3, 0, 0).unwrap()))
+            jp_to_local(NaiveDate::from_ymd_opt(2023, 11, 24).unwrap(), NaiveTime::from_hms_opt(3, 0, 0).unwrap())
         );
 
         assert_eq!(
@@ -284,4 +445,189 @@ mod test {
             (NaiveDate::from_ymd_opt(2023, 11, 26).unwrap(), None)
         );
     }
+
+    /// 東京時間で与えた現在時刻
+    fn fixed_now_jp(year: i32, month: u32, day: u32, hour: u32, min: u32) -> NaiveDateTime {
+        let clock = FixedClock::new(
+            Tokyo
+                .with_ymd_and_hms(year, month, day, hour, min, 0)
+                .unwrap()
+                .to_utc(),
+        );
+        now_jp(&clock)
+    }
+
+    fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn hm(hour: u32, min: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_time_parser_new_year() {
+        let now_jp = fixed_now_jp(2024, 1, 2, 10, 0);
+
+        // 年末のポストは前年になる
+        for (datetime_str, expected) in [
+            ("12月30日(土)21:00", ymd(2023, 12, 30)),
+            ("12月31日(日)23:59", ymd(2023, 12, 31)),
+            ("1月1日(月)0:00", ymd(2024, 1, 1)),
+            ("1月2日(火)9:59", ymd(2024, 1, 2)),
+            ("1月3日(火)12:00", ymd(2023, 1, 3)),
+        ] {
+            let (hour, min) = {
+                let (_, time) = datetime_str.split_once(')').unwrap();
+                let (hour, min) = time.split_once(':').unwrap();
+                (hour.parse().unwrap(), min.parse().unwrap())
+            };
+            assert_eq!(
+                yahoojp_time_parser(datetime_str, now_jp).unwrap(),
+                jp_to_local(expected, hm(hour, min)),
+                "{}",
+                datetime_str
+            );
+        }
+
+        // 大晦日から見た年始
+        let now_jp = fixed_now_jp(2023, 12, 31, 23, 30);
+        assert_eq!(
+            yahoojp_time_parser("1月1日(日)10:00", now_jp).unwrap(),
+            jp_to_local(ymd(2023, 1, 1), hm(10, 0))
+        );
+
+        // 未来の日時，曜日の一致しない日時はエラー
+        let now_jp = fixed_now_jp(2024, 1, 2, 10, 0);
+        for datetime_str in [
+            "1月2日(火)10:01",
+            "1月3日(水)12:00",
+            "12月30日(金)21:00",
+            "12月32日(日)21:00",
+        ] {
+            assert!(
+                yahoojp_time_parser(datetime_str, now_jp).is_err(),
+                "{}",
+                datetime_str
+            );
+        }
+        assert!(yahoojp_time_parser("2024年1月3日", now_jp).is_err());
+        assert_eq!(
+            yahoojp_time_parser("2023年12月30日", now_jp).unwrap(),
+            (ymd(2023, 12, 30), None)
+        );
+    }
+
+    #[test]
+    fn test_time_parser_leap_day() {
+        // 閏年の当年
+        assert_eq!(
+            yahoojp_time_parser("2月29日(木)12:00", fixed_now_jp(2024, 3, 1, 0, 0)).unwrap(),
+            jp_to_local(ymd(2024, 2, 29), hm(12, 0))
+        );
+        // 閏年の翌年
+        for now_jp in [
+            fixed_now_jp(2025, 1, 5, 0, 0),
+            fixed_now_jp(2025, 3, 1, 0, 0),
+        ] {
+            assert_eq!(
+                yahoojp_time_parser("2月29日(木)12:00", now_jp).unwrap(),
+                jp_to_local(ymd(2024, 2, 29), hm(12, 0))
+            );
+        }
+        // 近くに閏日が無い
+        assert!(yahoojp_time_parser("2月29日(木)12:00", fixed_now_jp(2026, 3, 1, 0, 0)).is_err());
+        // 閏日の前日から見た閏日
+        assert!(yahoojp_time_parser("2月29日(木)12:00", fixed_now_jp(2024, 2, 28, 12, 0)).is_err());
+        // 閏日の翌日
+        assert_eq!(
+            yahoojp_time_parser("3月1日(金)0:30", fixed_now_jp(2024, 3, 1, 1, 0)).unwrap(),
+            jp_to_local(ymd(2024, 3, 1), hm(0, 30))
+        );
+    }
+
+    /// テスト用のポストのhtml
+    fn post_html(author: &str, datetime: &str, content: &str) -> String {
+        format!(
+            r#"<div class="Tweet_bodyContainer__x"><span class="Tweet_authorName__x">{}</span><time class="Tweet_time__x"><a href="https://x.com/{}/status/1729">{}</a></time><div class="Tweet_body__x">{}</div></div>"#,
+            author, author, datetime, content
+        )
+    }
+
+    #[test]
+    fn test_parse_partial() {
+        let clock = FixedClock::new(
+            Tokyo
+                .with_ymd_and_hms(2023, 11, 27, 12, 0, 0)
+                .unwrap()
+                .to_utc(),
+        );
+        let source = format!(
+            "<html><body>{}{}{}</body></html>",
+            post_html("alice", "5分前", "Rust CLI"),
+            r#"<div class="Tweet_bodyContainer__ad"><div class="Tweet_body__x">ad</div></div>"#,
+            post_html("bob", "そのうち", "TUI"),
+        );
+
+        let output = YahooJpParser::parse_partial(source.clone(), &clock).unwrap();
+        assert_eq!(output.posts.len(), 1);
+        assert_eq!(output.posts[0].author, "alice");
+        assert_eq!(output.posts[0].id, "1729");
+        assert_eq!(output.diagnostics.len(), 2);
+        assert_eq!(output.diagnostics[0].index, 1);
+        assert!(matches!(
+            output.diagnostics[0].issue,
+            ParseIssue::MissingSelector { .. }
+        ));
+        assert!(output.diagnostics[0]
+            .snippet
+            .contains("Tweet_bodyContainer__ad"));
+        assert_eq!(
+            output.diagnostics[1].issue,
+            ParseIssue::InvalidDatetime {
+                datetime_str: "そのうち".to_string(),
+                message: "Error::ParseDatetimeError: Unexpected string: そのうち".to_string(),
+            }
+        );
+
+        assert!(YahooJpParser::parse(source.clone(), &clock, &ErrorBudget::MaxCount(2)).is_ok());
+        assert!(YahooJpParser::parse(source, &clock, &ErrorBudget::MaxCount(1)).is_err());
+    }
+
+    #[test]
+    fn test_parse_page_status() {
+        let clock = FixedClock::new(
+            Tokyo
+                .with_ymd_and_hms(2023, 11, 27, 12, 0, 0)
+                .unwrap()
+                .to_utc(),
+        );
+
+        let results = format!(
+            "<html><body>{}</body></html>",
+            post_html("alice", "5分前", "Rust CLI")
+        );
+        assert_eq!(
+            YahooJpParser::parse_partial(results, &clock)
+                .unwrap()
+                .page_status,
+            PageStatus::Results
+        );
+
+        let no_hit = "<html><body><p>「Rust」に一致するポストはありません</p></body></html>";
+        let output = YahooJpParser::parse_partial(no_hit.to_string(), &clock).unwrap();
+        assert_eq!(output.page_status, PageStatus::NoResults);
+        assert!(output.posts.is_empty());
+
+        let captcha = r#"<html><body><div id="captcha">ロボットではありません</div></body></html>"#;
+        assert!(matches!(
+            YahooJpParser::parse_partial(captcha.to_string(), &clock),
+            Err(Error::BlockedPageError(_))
+        ));
+
+        let drifted = r#"<html><body><div class="Post_container__x">Rust</div></body></html>"#;
+        let err = YahooJpParser::parse_partial(drifted.to_string(), &clock).unwrap_err();
+        assert!(matches!(err, Error::UnexpectedStructureError { .. }));
+        assert!(err.is_page_structure_error());
+    }
 }
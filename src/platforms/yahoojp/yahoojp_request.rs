@@ -1,4 +1,5 @@
 use crate::error::Error;
+use crate::request::check_status;
 use crate::RequestSource;
 
 use reqwest::Client;
@@ -18,7 +19,7 @@ impl RequestSource for YahooJpRequest {
         );
 
         info!("Attempting request to {}.", url);
-        let res = Client::new().get(&url).send().await?;
+        let res = check_status(Client::new().get(&url).send().await?)?;
         let text = res.text().await?;
 
         info!("Finished request to {}.", url);
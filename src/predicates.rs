@@ -1,7 +1,9 @@
-use crate::Posts;
+use crate::{Clock, Posts};
 
-use chrono::{Duration, Local, NaiveDateTime};
+use chrono::{Duration, NaiveDateTime};
+use serde::Serialize;
 use std::collections::VecDeque;
+use std::sync::Arc;
 
 /// 時間当たりのポストの個数で判定
 pub struct NumberPerDuration {
@@ -17,7 +19,7 @@ impl NumberPerDuration {
     pub fn predicate(&self, posts: &Posts) -> bool {
         let mut datetimes = posts
             .iter()
-            .filter_map(|post| (&post.time).map(|time| NaiveDateTime::new(post.date, time)))
+            .filter_map(|post| post.time.map(|time| NaiveDateTime::new(post.date, time)))
             .collect::<Vec<_>>();
 
         datetimes.sort();
@@ -25,14 +27,14 @@ impl NumberPerDuration {
         let mut queue = VecDeque::<&NaiveDateTime>::with_capacity(self.n);
 
         for datetime in datetimes.iter() {
-            queue.push_back(&datetime);
+            queue.push_back(datetime);
 
             if queue.len() > self.n {
                 queue.pop_front();
             }
 
             if queue.len() == self.n
-                && **queue.get(self.n - 1).unwrap() - **queue.get(0).unwrap() < self.duration
+                && **queue.get(self.n - 1).unwrap() - **queue.front().unwrap() < self.duration
             {
                 return true;
             }
@@ -45,20 +47,21 @@ impl NumberPerDuration {
 /// 最後の(最新の)投稿時間で判定
 pub struct LatestPostTime {
     duration: Duration,
+    clock: Arc<dyn Clock>,
 }
 
 impl LatestPostTime {
-    pub fn new(duration: Duration) -> Self {
-        Self { duration }
+    pub fn new(duration: Duration, clock: Arc<dyn Clock>) -> Self {
+        Self { duration, clock }
     }
 
     /// 判定用のメソッド．O(N)
     pub fn predicate(&self, posts: &Posts) -> bool {
-        let now = Local::now().naive_local();
+        let now = self.clock.now_local().naive_local();
 
         if let Some(latest_post_time) = posts
             .iter()
-            .filter_map(|post| (&post.time).map(|time| NaiveDateTime::new(post.date, time)))
+            .filter_map(|post| post.time.map(|time| NaiveDateTime::new(post.date, time)))
             .max()
         {
             (now - latest_post_time) < self.duration
@@ -88,9 +91,20 @@ impl ContainsKeyWords {
     }
 }
 
+/// ボックス化したpred関数
+type BoxedPred = Box<dyn Fn(&Posts) -> bool + Send + Sync>;
+
+/// 名前付きのpred関数の判定結果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Verdict {
+    pub name: String,
+    pub matched: bool,
+}
+
 /// 各種pred関数のリスト(Any)
+#[derive(Default)]
 pub struct PredListAny {
-    inner_list: Vec<Box<dyn Fn(&Posts) -> bool + Send + Sync>>,
+    inner_list: Vec<(String, BoxedPred)>,
 }
 
 impl PredListAny {
@@ -99,13 +113,27 @@ impl PredListAny {
             inner_list: Vec::new(),
         }
     }
-    pub fn append_pred<P: Fn(&Posts) -> bool + Send + Sync + 'static>(&mut self, pred: P) {
+    pub fn append_pred<S: Into<String>, P: Fn(&Posts) -> bool + Send + Sync + 'static>(
+        &mut self,
+        name: S,
+        pred: P,
+    ) {
         self.inner_list
-            .push(Box::new(pred) as Box<dyn Fn(&Posts) -> bool + Send + Sync>);
+            .push((name.into(), Box::new(pred) as BoxedPred));
     }
     /// 判定用のメソッド．
     pub fn predicate(&self, posts: &Posts) -> bool {
-        self.inner_list.iter().any(|pred| pred(posts))
+        self.inner_list.iter().any(|(_, pred)| pred(posts))
+    }
+    /// 全てのpred関数の判定結果を取得する．
+    pub fn verdicts(&self, posts: &Posts) -> Vec<Verdict> {
+        self.inner_list
+            .iter()
+            .map(|(name, pred)| Verdict {
+                name: name.clone(),
+                matched: pred(posts),
+            })
+            .collect()
     }
 }
 
@@ -117,10 +145,39 @@ macro_rules! pred_list_any {
             let mut list = $crate::predicates::PredListAny::new();
 
             $(
-                list.append_pred($pred);
+                list.append_pred(stringify!($pred), $pred);
             )*
 
             list
         }
     };
 }
+
+#[cfg(test)]
+mod test {
+    use super::LatestPostTime;
+    use crate::clock::FixedClock;
+    use crate::Post;
+
+    use chrono::{Duration, Local, TimeZone};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_latest_post_time() {
+        let now = Local.with_ymd_and_hms(2023, 11, 27, 12, 0, 0).unwrap();
+        let clock = Arc::new(FixedClock::new(now.to_utc()));
+
+        let posted = (now - Duration::minutes(30)).naive_local();
+        let posts = vec![Post {
+            date: posted.date(),
+            time: Some(posted.time()),
+            ..Default::default()
+        }];
+
+        let pred = LatestPostTime::new(Duration::hours(1), clock.clone());
+        assert!(pred.predicate(&posts));
+
+        clock.advance(Duration::hours(1));
+        assert!(!pred.predicate(&posts));
+    }
+}
@@ -1,45 +1,397 @@
+mod atomic_file;
+mod command_reporter;
+mod digest_reporter;
+mod email_reporter;
+mod feed_reporter;
+mod file_save_reporter;
 mod json_save_reporter;
 mod notification_reporter;
+mod retention;
+mod sqlite_reporter;
+mod template;
+mod webhook_reporter;
 
+pub use command_reporter::CommandReporter;
+pub use digest_reporter::{DigestFormat, DigestReporter};
+pub use email_reporter::{EmailReporter, SmtpSecurity};
+pub use feed_reporter::{FeedFormat, FeedReporter};
+pub use file_save_reporter::{FileSaveReporter, SaveFormat};
 pub use json_save_reporter::JsonSaveReporter;
 pub use notification_reporter::LatestPostNotificationReporter;
 pub use notification_reporter::StaticNotificationReporter;
+pub use retention::RetentionPolicy;
+pub use sqlite_reporter::SqliteReporter;
+pub use template::ReportTemplate;
+pub use webhook_reporter::WebhookReporter;
 
+use crate::clock::{Clock, SystemClock};
 use crate::error::Error;
-use crate::Posts;
+use crate::predicates::Verdict;
+use crate::retry::RetryPolicy;
+use crate::{Post, Posts};
 
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+/// htmlの特殊文字をエスケープする．
+pub(crate) fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// ファイル名に使えない文字(パスの区切り・`%`など)を`_`に置き換える．
+pub(crate) fn file_name_safe(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// 時間の分かるポストのうち最新のものとその日時を取得する．
+pub(crate) fn latest_post(posts: &Posts) -> Option<(&Post, NaiveDateTime)> {
+    posts
+        .iter()
+        .filter_map(|post| {
+            post.time
+                .map(|time| (post, NaiveDateTime::new(post.date, time)))
+        })
+        .max_by(|(_, x), (_, y)| x.cmp(y))
+}
+
+/// リポートの内容
+#[derive(Debug, Clone, Copy)]
+pub struct ReportContext<'a> {
+    /// ジョブの名前
+    pub job_name: Option<&'a str>,
+    /// 検索したプラットフォームの名前
+    pub platform: &'a str,
+    /// 検索したキーワード
+    pub keywords: &'a [String],
+    pub posts: &'a Posts,
+    /// 各pred関数の判定結果
+    pub verdicts: &'a [Verdict],
+}
+
+/// 別スレッドで処理するために所有権を持つリポートの内容
+pub(crate) struct OwnedReportContext {
+    job_name: Option<String>,
+    platform: String,
+    keywords: Vec<String>,
+    posts: Posts,
+    verdicts: Vec<Verdict>,
+}
+
+impl OwnedReportContext {
+    pub fn new(context: &ReportContext<'_>) -> Self {
+        Self {
+            job_name: context.job_name.map(str::to_string),
+            platform: context.platform.to_string(),
+            keywords: context.keywords.to_vec(),
+            posts: context.posts.clone(),
+            verdicts: context.verdicts.to_vec(),
+        }
+    }
+
+    pub fn as_context(&self) -> ReportContext<'_> {
+        ReportContext {
+            job_name: self.job_name.as_deref(),
+            platform: &self.platform,
+            keywords: &self.keywords,
+            posts: &self.posts,
+            verdicts: &self.verdicts,
+        }
+    }
+}
+
+/// ファイルの書き込みなどブロッキングする処理を別スレッドで実行する．
+/// 他のリポーターの並行な実行やタイムアウトを妨げないようにする．
+/// タイムアウトした場合も処理は中断されずに完了する．
+pub(crate) async fn run_blocking<F>(f: F) -> Result<(), Error>
+where
+    F: FnOnce() -> Result<(), Error> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await?
+}
+
+impl<'a> ReportContext<'a> {
+    /// ポストのみからなるリポートの内容
+    pub fn from_posts(posts: &'a Posts) -> Self {
+        Self {
+            job_name: None,
+            platform: "",
+            keywords: &[],
+            posts,
+            verdicts: &[],
+        }
+    }
+}
 
 /// リポートを行うトレイト
 #[async_trait::async_trait]
-pub trait Report {
-    async fn report(&self, posts: &Posts) -> Result<(), Error>;
+pub trait Report: Sync {
+    async fn report(&self, context: &ReportContext<'_>) -> Result<(), Error>;
+
+    /// 条件の成否に関わらず検索の度に呼ばれる記録．デフォルトでは何もしない．
+    async fn record(&self, _context: &ReportContext<'_>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// ページの構造が認識できないなど，検索自体が失敗した場合のリポート．
+    /// `context`はジョブの情報のみでポストを含まない．デフォルトでは何もしない．
+    async fn report_failure(
+        &self,
+        _context: &ReportContext<'_>,
+        _error: &Error,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// `report_failure`でリポートを送信するかどうか．メトリクスでは送信するリポーターのみを
+    /// 数える．デフォルトでは`false`．
+    fn reports_failure(&self) -> bool {
+        false
+    }
+
+    /// 終了前に保持している状態を書き出す．デフォルトでは何もしない．
+    async fn flush(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// 同じリポートを繰り返しても結果が変わらないかどうか．`false`の場合，
+    /// 送信済みの可能性があるタイムアウトは再試行しない．デフォルトでは`true`．
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+
+    /// エラーの表示などに用いるリポーターの名前．デフォルトでは型名．
+    fn name(&self) -> &str {
+        let type_name = std::any::type_name::<Self>();
+        type_name.rsplit("::").next().unwrap_or(type_name)
+    }
+}
+
+/// 複数のリポーターの実行方針
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportPolicy {
+    /// 全てのリポーターを並行に実行し，失敗したものをまとめてエラーとする．
+    #[default]
+    BestEffort,
+    /// 順番に実行し，一つでも失敗した時点で以降のリポーターを実行しない．
+    /// それまでに成功したリポートは取り消さないため，全てか無しかにはならない．
+    FailFast,
+}
+
+/// タイムアウトを設けて実行する．
+async fn run_with_timeout(
+    timeout: Option<Duration>,
+    future: Pin<Box<dyn Future<Output = Result<(), Error>> + Send + '_>>,
+) -> Result<(), Error> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, future)
+            .await
+            .unwrap_or_else(|_| {
+                Err(Error::TimeoutError(format!(
+                    "timed out after {:?}",
+                    timeout
+                )))
+            }),
+        None => future.await,
+    }
 }
 
 /// リポーターのリスト
 pub struct ReporterList {
     inner_list: Vec<Arc<dyn Report + Send + Sync>>,
+    timeout: Option<Duration>,
+    policy: ReportPolicy,
+    retry: RetryPolicy,
+    clock: Arc<dyn Clock>,
+    /// メトリクスの`job`ラベルとするジョブの名前
+    job_name: Option<String>,
+}
+
+impl Default for ReporterList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ReporterList {
+    /// デフォルトのリポーターごとのタイムアウト
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
     pub fn new() -> Self {
         Self {
             inner_list: Vec::new(),
+            timeout: Some(Self::DEFAULT_TIMEOUT),
+            policy: ReportPolicy::default(),
+            retry: RetryPolicy::default(),
+            clock: Arc::new(SystemClock),
+            job_name: None,
         }
     }
     pub fn append_reporter<R: Report + Send + Sync + 'static>(&mut self, reporter: R) {
         self.inner_list
             .push(Arc::new(reporter) as Arc<dyn Report + Send + Sync>);
     }
+    /// リポーターごとのタイムアウトを設定する．`None`の場合はタイムアウトしない．
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    /// 実行方針を設定する．
+    pub fn with_policy(mut self, policy: ReportPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// リポーターごとの再試行の設定．タイムアウトは一回の試行ごとに適用される．
+    /// デフォルトは`RetryPolicy::default()`．
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+    /// Retry-Afterの日時の解釈に用いる時計を設定する．
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+    /// メトリクスでリポートの結果を記録するジョブの名前を設定する．
+    pub fn with_job_name<S: Into<String>>(mut self, job_name: S) -> Self {
+        self.job_name = Some(job_name.into());
+        self
+    }
+
+    /// 一つのリポーターで`f`を実行する．失敗した場合はリポーターの名前を含むメッセージを返す．
+    /// `counted`の場合は送信の結果をメトリクスに記録する．
+    async fn run_reporter<'a, F>(
+        &self,
+        reporter: &'a (dyn Report + Send + Sync),
+        f: &F,
+        counted: bool,
+    ) -> Result<(), String>
+    where
+        F: Fn(
+            &'a (dyn Report + Send + Sync),
+        ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>>,
+    {
+        let res = self
+            .retry
+            .run_if(
+                reporter.name(),
+                self.clock.as_ref(),
+                |e| {
+                    e.is_retryable()
+                        && (reporter.is_idempotent() || !matches!(e, Error::TimeoutError(_)))
+                },
+                || run_with_timeout(self.timeout, f(reporter)),
+            )
+            .await;
+
+        if counted {
+            crate::metrics::global().record_report(
+                self.job_name.as_deref().unwrap_or_default(),
+                reporter.name(),
+                res.is_ok(),
+            );
+        }
+
+        res.map_err(|e| {
+            warn!("Reporter {} failed. {}", reporter.name(), e);
+            format!("{}: {}", reporter.name(), e)
+        })
+    }
+
+    /// 方針に従って全てのリポーターで`f`を実行する．`counted`を満たすリポーターの結果は
+    /// メトリクスに記録する．
+    async fn run_all<'a, F>(
+        &'a self,
+        f: F,
+        counted: fn(&(dyn Report + Send + Sync)) -> bool,
+    ) -> Result<(), Error>
+    where
+        F: Fn(
+            &'a (dyn Report + Send + Sync),
+        ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>>,
+    {
+        let failures = match self.policy {
+            ReportPolicy::BestEffort => {
+                futures::future::join_all(self.inner_list.iter().map(|reporter| {
+                    self.run_reporter(reporter.as_ref(), &f, counted(reporter.as_ref()))
+                }))
+                .await
+                .into_iter()
+                .filter_map(Result::err)
+                .collect::<Vec<_>>()
+            }
+            ReportPolicy::FailFast => {
+                let mut failures = Vec::new();
+                for reporter in self.inner_list.iter() {
+                    if let Err(failure) = self
+                        .run_reporter(reporter.as_ref(), &f, counted(reporter.as_ref()))
+                        .await
+                    {
+                        failures.push(failure);
+                        break;
+                    }
+                }
+                failures
+            }
+        };
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::ReportError { failures })
+        }
+    }
 }
 
 #[async_trait::async_trait]
 impl Report for ReporterList {
-    async fn report(&self, posts: &Posts) -> Result<(), Error> {
-        for reporter in self.inner_list.iter() {
-            reporter.report(posts).await?;
-        }
-        Ok(())
+    async fn report(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        self.run_all(|reporter| reporter.report(context), |_| true)
+            .await
+    }
+
+    async fn record(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        self.run_all(|reporter| reporter.record(context), |_| false)
+            .await
+    }
+
+    async fn report_failure(
+        &self,
+        context: &ReportContext<'_>,
+        error: &Error,
+    ) -> Result<(), Error> {
+        self.run_all(
+            |reporter| reporter.report_failure(context, error),
+            |reporter| reporter.reports_failure(),
+        )
+        .await
+    }
+
+    fn reports_failure(&self) -> bool {
+        self.inner_list
+            .iter()
+            .any(|reporter| reporter.reports_failure())
+    }
+
+    async fn flush(&self) -> Result<(), Error> {
+        self.run_all(|reporter| reporter.flush(), |_| false).await
     }
 }
 
@@ -58,3 +410,173 @@ macro_rules! reporter_list {
         }
     };
 }
+
+#[cfg(test)]
+mod test {
+    use super::{run_blocking, Report, ReportContext, ReportPolicy, ReporterList};
+    use crate::error::Error;
+    use crate::retry::RetryPolicy;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// 呼ばれた回数を数え，指定した結果を返すリポーター
+    struct MockReporter {
+        name: &'static str,
+        delay: Duration,
+        fail: bool,
+        count: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Report for MockReporter {
+        async fn report(&self, _: &ReportContext<'_>) -> Result<(), Error> {
+            tokio::time::sleep(self.delay).await;
+            self.count.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                Err(Error::OsError("no session".to_string()))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn name(&self) -> &str {
+            self.name
+        }
+    }
+
+    fn reporter_list(policy: ReportPolicy, count: &Arc<AtomicUsize>) -> ReporterList {
+        let mut list = ReporterList::new()
+            .with_timeout(Some(Duration::from_millis(100)))
+            .with_policy(policy)
+            .with_retry(RetryPolicy::NONE);
+        for (name, delay, fail) in [
+            ("failing", 0, true),
+            ("slow", 1000, false),
+            ("ok", 0, false),
+        ] {
+            list.append_reporter(MockReporter {
+                name,
+                delay: Duration::from_millis(delay),
+                fail,
+                count: Arc::clone(count),
+            });
+        }
+        list
+    }
+
+    #[tokio::test]
+    async fn test_reporter_list_best_effort() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let list = reporter_list(ReportPolicy::BestEffort, &count);
+        assert!(!list.reports_failure());
+
+        let posts = Vec::new();
+        match list.report(&ReportContext::from_posts(&posts)).await {
+            Err(Error::ReportError { failures }) => {
+                assert_eq!(failures.len(), 2);
+                assert!(failures[0].starts_with("failing: Error::OsError"));
+                assert!(failures[1].starts_with("slow: Error::TimeoutError"));
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+        // 失敗したリポーターとタイムアウトしたリポーター以外も実行される
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_reporter_list_fail_fast() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let list = reporter_list(ReportPolicy::FailFast, &count);
+
+        let posts = Vec::new();
+        match list.report(&ReportContext::from_posts(&posts)).await {
+            Err(Error::ReportError { failures }) => assert_eq!(failures.len(), 1),
+            res => panic!("unexpected result: {:?}", res),
+        }
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    /// 呼ばれる度にタイムアウトするリポーター
+    struct TimeoutReporter {
+        idempotent: bool,
+        count: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Report for TimeoutReporter {
+        async fn report(&self, _: &ReportContext<'_>) -> Result<(), Error> {
+            self.count.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(1000)).await;
+            Ok(())
+        }
+
+        fn is_idempotent(&self) -> bool {
+            self.idempotent
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reporter_list_retry() {
+        let retry = RetryPolicy {
+            max_retries: 2,
+            initial_delay_ms: 1,
+            max_delay_ms: 1,
+        };
+
+        for (idempotent, expected) in [(true, 3), (false, 1)] {
+            let count = Arc::new(AtomicUsize::new(0));
+            let mut list = ReporterList::new()
+                .with_timeout(Some(Duration::from_millis(10)))
+                .with_retry(retry);
+            list.append_reporter(TimeoutReporter {
+                idempotent,
+                count: Arc::clone(&count),
+            });
+
+            let posts = Vec::new();
+            assert!(list
+                .report(&ReportContext::from_posts(&posts))
+                .await
+                .is_err());
+            // 冪等でないリポーターのタイムアウトは再試行しない
+            assert_eq!(count.load(Ordering::SeqCst), expected);
+        }
+    }
+
+    /// スレッドをブロッキングするリポーター
+    struct BlockingReporter;
+
+    #[async_trait::async_trait]
+    impl Report for BlockingReporter {
+        async fn report(&self, _: &ReportContext<'_>) -> Result<(), Error> {
+            run_blocking(|| {
+                std::thread::sleep(Duration::from_millis(1000));
+                Ok(())
+            })
+            .await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reporter_list_blocking() {
+        let mut list = ReporterList::new()
+            .with_timeout(Some(Duration::from_millis(100)))
+            .with_retry(RetryPolicy::NONE);
+        list.append_reporter(BlockingReporter);
+        list.append_reporter(BlockingReporter);
+
+        // ブロッキングする処理も並行に実行され，タイムアウトする
+        let started = std::time::Instant::now();
+        let posts = Vec::new();
+        match list.report(&ReportContext::from_posts(&posts)).await {
+            Err(Error::ReportError { failures }) => {
+                assert_eq!(failures.len(), 2);
+                assert!(failures[0].starts_with("BlockingReporter: Error::TimeoutError"));
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+        assert!(started.elapsed() < Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,125 @@
+use crate::error::Error;
+
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 同じプロセス内で一時ファイルの名前が重複しないための連番
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// 同じディレクトリの一時ファイルに書き込み，`commit`で書き込み先に置き換えるファイル．
+/// 書き込みの途中で終了しても書き込み先が不完全な内容になることはない．
+pub(crate) struct AtomicFile {
+    path: PathBuf,
+    temp_path: PathBuf,
+    file: Option<File>,
+}
+
+impl AtomicFile {
+    /// 一時ファイルを作製する．`append`の場合は書き込み先の既存の内容を引き継ぐ．
+    pub fn create(path: &Path, append: bool) -> Result<Self, Error> {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        // 拡張子を`.tmp`として保持方針の対象から外す
+        let temp_path = path.with_file_name(format!(
+            ".{}.{}.{}.tmp",
+            file_name,
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        if append && path.is_file() {
+            std::fs::copy(path, &temp_path)?;
+        }
+        let file = File::options().create(true).append(true).open(&temp_path)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            temp_path,
+            file: Some(file),
+        })
+    }
+
+    /// 一時ファイル
+    pub fn as_file(&self) -> &File {
+        self.file.as_ref().expect("file exists until commit")
+    }
+
+    /// 内容をディスクに書き出し，書き込み先を置き換える．
+    pub fn commit(mut self) -> Result<(), Error> {
+        if let Some(file) = self.file.take() {
+            file.sync_all()?;
+        }
+        std::fs::rename(&self.temp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file
+            .as_mut()
+            .expect("file exists until commit")
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file
+            .as_mut()
+            .expect("file exists until commit")
+            .flush()
+    }
+}
+
+/// `commit`しなかった場合は一時ファイルを削除する．
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            let _ = std::fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+/// 内容を一時ファイルに書き込んでから置き換える．
+pub(crate) fn write_atomic<C: AsRef<[u8]>>(path: &Path, contents: C) -> Result<(), Error> {
+    let mut file = AtomicFile::create(path, false)?;
+    file.write_all(contents.as_ref())?;
+    file.commit()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{write_atomic, AtomicFile};
+
+    use std::io::Write;
+
+    #[test]
+    fn test_atomic_file() {
+        let dir_path =
+            std::env::temp_dir().join(format!("search_and_report_atomic_{}", std::process::id()));
+        std::fs::create_dir_all(&dir_path).unwrap();
+        let path = dir_path.join("report.jsonl");
+
+        write_atomic(&path, "1\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "1\n");
+
+        // commitするまで書き込み先は変わらない
+        let mut file = AtomicFile::create(&path, true).unwrap();
+        file.write_all(b"2\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "1\n");
+        file.commit().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "1\n2\n");
+
+        // 中断した場合は一時ファイルを残さない
+        let mut file = AtomicFile::create(&path, false).unwrap();
+        file.write_all(b"3\n").unwrap();
+        drop(file);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "1\n2\n");
+        assert_eq!(std::fs::read_dir(&dir_path).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&dir_path).unwrap();
+    }
+}
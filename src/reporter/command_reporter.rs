@@ -0,0 +1,244 @@
+use super::ReportContext;
+use crate::error::Error;
+use crate::Report;
+
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{debug, info};
+
+/// エラーに含める標準エラー出力の最大の文字数
+const MAX_STDERR_LENGTH: usize = 1000;
+
+/// 外部コマンドを実行するリポーター．ポストをjsonとして標準入力に渡し，
+/// ジョブの情報を`SEARCH_AND_REPORT_*`の環境変数で渡す．
+pub struct CommandReporter {
+    program: String,
+    args: Vec<String>,
+    env: BTreeMap<String, String>,
+    timeout: Duration,
+}
+
+impl CommandReporter {
+    /// デフォルトのタイムアウト
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    pub fn new<S: Into<String>>(program: S) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            env: BTreeMap::new(),
+            timeout: Self::DEFAULT_TIMEOUT,
+        }
+    }
+    /// コマンドの引数を設定する．
+    pub fn with_args(mut self, args: &[String]) -> Self {
+        self.args = args.to_vec();
+        self
+    }
+    /// 追加の環境変数を設定する．
+    pub fn with_env(mut self, env: &BTreeMap<String, String>) -> Self {
+        self.env = env.clone();
+        self
+    }
+    /// タイムアウトを設定する．タイムアウトした場合はコマンドを終了させる．
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// ジョブの情報を表す環境変数
+    fn metadata_env(context: &ReportContext<'_>) -> Result<Vec<(&'static str, String)>, Error> {
+        Ok(vec![
+            (
+                "SEARCH_AND_REPORT_JOB_NAME",
+                context.job_name.unwrap_or_default().to_string(),
+            ),
+            ("SEARCH_AND_REPORT_PLATFORM", context.platform.to_string()),
+            (
+                "SEARCH_AND_REPORT_KEYWORDS",
+                serde_json::to_string(context.keywords)?,
+            ),
+            (
+                "SEARCH_AND_REPORT_POST_COUNT",
+                context.posts.len().to_string(),
+            ),
+            (
+                "SEARCH_AND_REPORT_MATCHED",
+                context
+                    .verdicts
+                    .iter()
+                    .filter(|verdict| verdict.matched)
+                    .map(|verdict| verdict.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+        ])
+    }
+}
+
+impl CommandReporter {
+    /// コマンドを実行する．`error`を渡した場合は`SEARCH_AND_REPORT_ERROR`に設定する．
+    async fn run(&self, context: &ReportContext<'_>, error: Option<&Error>) -> Result<(), Error> {
+        let input = serde_json::to_vec(context.posts)?;
+
+        info!("Running command: {}", self.program);
+        let mut command = Command::new(&self.program);
+        if let Some(error) = error {
+            command.env("SEARCH_AND_REPORT_ERROR", error.to_string());
+        }
+        let mut child = command
+            .args(&self.args)
+            .envs(&self.env)
+            .envs(Self::metadata_env(context)?)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| Error::CommandError(format!("{}: {}", self.program, e)))?;
+
+        // 標準入力を読まずに終了するコマンドもあるため，書き込みの失敗は無視する
+        let mut stdin = child.stdin.take();
+        let write_stdin = async move {
+            if let Some(stdin) = stdin.as_mut() {
+                let _ = stdin.write_all(&input).await;
+            }
+        };
+
+        let (_, output) = tokio::time::timeout(
+            self.timeout,
+            futures::future::join(write_stdin, child.wait_with_output()),
+        )
+        .await
+        .map_err(|_| {
+            Error::TimeoutError(format!(
+                "{} timed out after {:?}",
+                self.program, self.timeout
+            ))
+        })?;
+        let output = output?;
+
+        debug!(
+            "{}: {}",
+            self.program,
+            String::from_utf8_lossy(&output.stdout)
+        );
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr)
+                .chars()
+                .take(MAX_STDERR_LENGTH)
+                .collect::<String>();
+            Err(Error::CommandError(format!(
+                "{} exited with {}. {}",
+                self.program,
+                output.status,
+                stderr.trim()
+            )))
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Report for CommandReporter {
+    async fn report(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        self.run(context, None).await
+    }
+
+    /// 空のポストと`SEARCH_AND_REPORT_ERROR`でコマンドを実行する．
+    async fn report_failure(
+        &self,
+        context: &ReportContext<'_>,
+        error: &Error,
+    ) -> Result<(), Error> {
+        self.run(context, Some(error)).await
+    }
+
+    fn reports_failure(&self) -> bool {
+        true
+    }
+
+    /// 終了させる前にコマンドが通知などを行っている可能性があるため，タイムアウトは再試行しない．
+    fn is_idempotent(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::CommandReporter;
+    use crate::error::Error;
+    use crate::reporter::ReportContext;
+    use crate::{Post, Report};
+
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    fn sh(script: &str) -> CommandReporter {
+        CommandReporter::new("sh").with_args(&["-c".to_string(), script.to_string()])
+    }
+
+    #[tokio::test]
+    async fn test_command_reporter() {
+        let out_path = std::env::temp_dir().join(format!(
+            "search_and_report_command_{}.json",
+            std::process::id()
+        ));
+        let posts = vec![Post {
+            id: "1".to_string(),
+            author: "alice".to_string(),
+            content: "Rust CLI".to_string(),
+            ..Default::default()
+        }];
+        let context = ReportContext {
+            job_name: Some("rust"),
+            ..ReportContext::from_posts(&posts)
+        };
+
+        // 標準入力と環境変数
+        let env = [("OUT".to_string(), out_path.to_string_lossy().into_owned())]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
+        sh(r#"test "$SEARCH_AND_REPORT_JOB_NAME" = rust && cat > "$OUT""#)
+            .with_env(&env)
+            .report(&context)
+            .await
+            .unwrap();
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&out_path).unwrap()).unwrap();
+        assert_eq!(written, serde_json::to_value(&posts).unwrap());
+        std::fs::remove_file(&out_path).unwrap();
+
+        // 検索の失敗
+        sh(r#"test "$SEARCH_AND_REPORT_ERROR" = "Error::BlockedPageError: captcha""#)
+            .report_failure(&context, &Error::BlockedPageError("captcha".to_string()))
+            .await
+            .unwrap();
+        // 通常のリポートでは設定しない
+        sh(r#"test -z "$SEARCH_AND_REPORT_ERROR""#)
+            .report(&context)
+            .await
+            .unwrap();
+
+        // 終了コード
+        match sh("echo oops >&2; exit 3").report(&context).await {
+            Err(Error::CommandError(message)) => {
+                assert!(message.contains("exit status: 3"), "{}", message);
+                assert!(message.ends_with("oops"), "{}", message);
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+
+        // タイムアウト
+        let res = sh("sleep 5")
+            .with_timeout(Duration::from_millis(100))
+            .report(&context)
+            .await;
+        assert!(matches!(res, Err(Error::TimeoutError(_))), "{:?}", res);
+    }
+}
@@ -0,0 +1,426 @@
+use super::atomic_file::write_atomic;
+use super::{escape_html, file_name_safe, run_blocking, OwnedReportContext, ReportContext};
+use crate::Report;
+use crate::{error::Error, Clock};
+
+use minijinja::{context, Environment, Value};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::info;
+
+/// ダイジェストのファイル名の接頭辞．ジョブの名前がある場合はその後に付ける．
+const DIGEST_PREFIX: &str = "digest_";
+
+/// 索引のファイル名(拡張子を除く)．ジョブの名前がある場合はその後に付ける．
+const INDEX_STEM: &str = "index";
+
+/// ジョブの名前が無い場合のタイトル
+const DEFAULT_TITLE: &str = "Search and Report";
+
+const DIGEST_HTML: &str = r#"<!DOCTYPE html>
+<html lang="ja">
+<head>
+<meta charset="utf-8">
+<title>{{ title }}</title>
+<style>
+body { font-family: sans-serif; max-width: 48em; margin: auto; }
+article { border-bottom: 1px solid #ccc; padding: 0.5em 0; }
+.meta { color: #666; font-size: 0.9em; }
+.matched { color: #070; }
+.unmatched { color: #999; }
+mark { background: #ff0; }
+</style>
+</head>
+<body>
+<h1>{{ title }}</h1>
+<p class="meta">{{ created_at }} / {{ keywords | join(" ") }} / {{ posts | length }} posts / <a href="{{ index }}">index</a></p>
+<h2>Conditions</h2>
+<ul>
+{% for verdict in verdicts %}<li class="{{ "matched" if verdict.matched else "unmatched" }}">{{ verdict.name }}: {{ "matched" if verdict.matched else "not matched" }}</li>
+{% endfor %}</ul>
+<h2>Posts</h2>
+{% for post in posts %}<article>
+<div class="meta">{{ post.author }} {{ post.datetime }}</div>
+<p>{{ post.content }}</p>
+</article>
+{% endfor %}</body>
+</html>
+"#;
+
+const DIGEST_MD: &str = r#"# {{ title | markdown }}
+
+{{ created_at }} / {{ keywords | join(" ") | markdown }} / {{ posts | length }} posts / [index]({{ index }})
+
+## Conditions
+
+{% for verdict in verdicts %}- {{ verdict.name | markdown }}: {{ "matched" if verdict.matched else "not matched" }}
+{% endfor %}
+## Posts
+{% for post in posts %}
+### {{ post.author | markdown }} {{ post.datetime }}
+
+{{ post.content }}
+{% endfor %}"#;
+
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html lang="ja">
+<head>
+<meta charset="utf-8">
+<title>{{ title }}</title>
+</head>
+<body>
+<h1>{{ title }}</h1>
+<ul>
+{% for digest in digests %}<li><a href="{{ digest }}">{{ digest }}</a></li>
+{% endfor %}</ul>
+</body>
+</html>
+"#;
+
+const INDEX_MD: &str = r#"# {{ title | markdown }}
+
+{% for digest in digests %}- [{{ digest }}]({{ digest }})
+{% endfor %}"#;
+
+/// Markdownとして解釈される文字をエスケープする．埋め込まれたhtmlとして解釈されないよう`<`なども置き換える．
+fn escape_markdown(s: &str) -> String {
+    let escaped = s
+        .chars()
+        .fold(String::with_capacity(s.len()), |mut escaped, c| {
+            if matches!(
+                c,
+                '\\' | '`'
+                    | '*'
+                    | '_'
+                    | '{'
+                    | '}'
+                    | '['
+                    | ']'
+                    | '('
+                    | ')'
+                    | '#'
+                    | '+'
+                    | '-'
+                    | '.'
+                    | '!'
+                    | '|'
+                    | '~'
+            ) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            escaped
+        });
+    escape_html(&escaped)
+}
+
+/// ダイジェストの描画に用いる環境．htmlのテンプレートは自動でエスケープされる．
+/// Markdownのテンプレートでは`markdown`フィルターでエスケープする．
+static ENVIRONMENT: Lazy<Environment<'static>> = Lazy::new(|| {
+    let mut environment = Environment::new();
+    environment.add_filter("markdown", |s: &str| escape_markdown(s));
+    environment
+        .add_template("digest.html", DIGEST_HTML)
+        .expect("digest template is valid");
+    environment
+        .add_template("digest.md", DIGEST_MD)
+        .expect("digest template is valid");
+    environment
+        .add_template("index.html", INDEX_HTML)
+        .expect("index template is valid");
+    environment
+        .add_template("index.md", INDEX_MD)
+        .expect("index template is valid");
+    environment
+});
+
+/// ダイジェストの形式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFormat {
+    /// 単体で閲覧できるhtml
+    #[default]
+    Html,
+    /// Markdown
+    Markdown,
+}
+
+impl DigestFormat {
+    /// ファイルの拡張子
+    fn extension(&self) -> &'static str {
+        match self {
+            DigestFormat::Html => "html",
+            DigestFormat::Markdown => "md",
+        }
+    }
+
+    /// 内容をエスケープした上でキーワードを強調する．
+    fn highlight(&self, content: &str, keywords: Option<&Regex>) -> Value {
+        let (escape, open, close): (fn(&str) -> String, _, _) = match self {
+            DigestFormat::Html => (escape_html, "<mark>", "</mark>"),
+            DigestFormat::Markdown => (escape_markdown, "**", "**"),
+        };
+
+        let mut highlighted = String::new();
+        let mut last = 0;
+        for keyword in keywords
+            .into_iter()
+            .flat_map(|regex| regex.find_iter(content))
+        {
+            highlighted.push_str(&escape(&content[last..keyword.start()]));
+            highlighted.push_str(open);
+            highlighted.push_str(&escape(keyword.as_str()));
+            highlighted.push_str(close);
+            last = keyword.end();
+        }
+        highlighted.push_str(&escape(&content[last..]));
+
+        match self {
+            DigestFormat::Html => Value::from_safe_string(highlighted.replace('\n', "<br>")),
+            DigestFormat::Markdown => Value::from(highlighted.replace('\n', "  \n")),
+        }
+    }
+}
+
+/// ポストをhtml・Markdownのダイジェストとして保存し，過去のダイジェストへの索引を更新するリポーター．
+/// 同じディレクトリを複数のジョブで共有できるよう，ファイル名と索引はジョブの名前ごとに分ける．
+#[derive(Clone)]
+pub struct DigestReporter {
+    dir_path: PathBuf,
+    clock: Arc<dyn Clock>,
+    formats: Vec<DigestFormat>,
+}
+
+impl DigestReporter {
+    pub fn new<P: Into<PathBuf>>(dir_path: P, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            dir_path: dir_path.into(),
+            clock,
+            formats: vec![DigestFormat::default()],
+        }
+    }
+    /// 作製する形式を設定する．
+    pub fn with_formats(mut self, formats: &[DigestFormat]) -> Self {
+        self.formats = formats.to_vec();
+        self
+    }
+
+    /// ダイジェスト・索引のファイル名の接頭辞
+    fn file_prefix(context: &ReportContext<'_>) -> String {
+        context
+            .job_name
+            .map(|name| format!("{}_", file_name_safe(name)))
+            .unwrap_or_default()
+    }
+
+    /// 索引のファイル名
+    fn index_name(format: DigestFormat, context: &ReportContext<'_>) -> String {
+        format!(
+            "{}{}.{}",
+            Self::file_prefix(context),
+            INDEX_STEM,
+            format.extension()
+        )
+    }
+
+    /// ダイジェストを描画する．
+    fn render_digest(
+        &self,
+        format: DigestFormat,
+        context: &ReportContext<'_>,
+    ) -> Result<String, Error> {
+        // 空白を含むキーワードは語ごとに強調する
+        let keywords = context
+            .keywords
+            .iter()
+            .flat_map(|keyword| keyword.split_whitespace())
+            .map(regex::escape)
+            .collect::<Vec<_>>();
+        let keywords = if keywords.is_empty() {
+            None
+        } else {
+            Some(Regex::new(&keywords.join("|"))?)
+        };
+        let posts = context
+            .posts
+            .iter()
+            .map(|post| {
+                let datetime = match post.time {
+                    Some(time) => format!("{} {}", post.date, time),
+                    None => post.date.to_string(),
+                };
+                context! {
+                    author => post.author,
+                    datetime => datetime,
+                    content => format.highlight(&post.content, keywords.as_ref()),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let template = ENVIRONMENT.get_template(&format!("digest.{}", format.extension()))?;
+        Ok(template.render(context! {
+            title => context.job_name.unwrap_or(DEFAULT_TITLE),
+            created_at => self.clock.now_local().format("%Y-%m-%d %H:%M:%S").to_string(),
+            keywords => context.keywords,
+            verdicts => context.verdicts,
+            posts => posts,
+            index => Self::index_name(format, context),
+        })?)
+    }
+
+    /// ディレクトリ内のこのジョブのダイジェストへの索引を描画する．新しいものから並べる．
+    fn render_index(
+        &self,
+        format: DigestFormat,
+        context: &ReportContext<'_>,
+    ) -> Result<String, Error> {
+        // 他のジョブのダイジェストを含めないよう，日時の部分まで一致を確かめる
+        let pattern = Regex::new(&format!(
+            r"^{}{}\d{{4}}(_\d{{2}}){{5}}\.{}$",
+            regex::escape(&Self::file_prefix(context)),
+            DIGEST_PREFIX,
+            format.extension()
+        ))?;
+        let mut digests = std::fs::read_dir(&self.dir_path)?
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter(|name| pattern.is_match(name))
+            .collect::<Vec<_>>();
+        digests.sort_by(|x, y| y.cmp(x));
+
+        let template = ENVIRONMENT.get_template(&format!("index.{}", format.extension()))?;
+        Ok(template.render(context! {
+            title => context.job_name.unwrap_or(DEFAULT_TITLE),
+            digests => digests,
+        })?)
+    }
+}
+
+impl DigestReporter {
+    /// ダイジェストと索引を書き込む．
+    fn write(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        // ディレクトリの存在確認，作製
+        if !self.dir_path.is_dir() {
+            std::fs::DirBuilder::new()
+                .recursive(true)
+                .create(&self.dir_path)?
+        }
+
+        let file_stem = format!(
+            "{}{}{}",
+            Self::file_prefix(context),
+            DIGEST_PREFIX,
+            self.clock.now_local().format("%Y_%m_%d_%H_%M_%S")
+        );
+
+        for format in self.formats.iter() {
+            let file_path = self
+                .dir_path
+                .join(format!("{}.{}", file_stem, format.extension()));
+            info!("Creating digest: {:?}", file_path);
+            write_atomic(&file_path, self.render_digest(*format, context)?)?;
+
+            let index_path = self.dir_path.join(Self::index_name(*format, context));
+            write_atomic(&index_path, self.render_index(*format, context)?)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Report for DigestReporter {
+    async fn report(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        let reporter = self.clone();
+        let context = OwnedReportContext::new(context);
+        run_blocking(move || reporter.write(&context.as_context())).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DigestFormat, DigestReporter};
+    use crate::clock::FixedClock;
+    use crate::predicates::Verdict;
+    use crate::reporter::ReportContext;
+    use crate::{Post, Report};
+
+    use chrono::{Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_digest_reporter() {
+        let dir_path =
+            std::env::temp_dir().join(format!("search_and_report_digest_{}", std::process::id()));
+        let clock = Arc::new(FixedClock::new(
+            Utc.with_ymd_and_hms(2023, 11, 27, 3, 0, 0).unwrap(),
+        ));
+        let reporter = DigestReporter::new(&dir_path, clock.clone())
+            .with_formats(&[DigestFormat::Html, DigestFormat::Markdown]);
+
+        let posts = vec![Post {
+            id: "1".to_string(),
+            author: "<alice>".to_string(),
+            date: NaiveDate::from_ymd_opt(2023, 11, 27).unwrap(),
+            time: Some(NaiveTime::from_hms_opt(12, 0, 0).unwrap()),
+            content: "Rust & CLI *1.0*".to_string(),
+        }];
+        let keywords = vec!["Rust CLI".to_string()];
+        let verdicts = vec![Verdict {
+            name: "condition_contain".to_string(),
+            matched: true,
+        }];
+        let context = ReportContext {
+            job_name: Some("rust"),
+            keywords: &keywords,
+            verdicts: &verdicts,
+            ..ReportContext::from_posts(&posts)
+        };
+
+        reporter.report(&context).await.unwrap();
+        clock.advance(Duration::minutes(1));
+        reporter.report(&context).await.unwrap();
+        // 同じディレクトリを共有する他のジョブ
+        let other = ReportContext {
+            job_name: Some("other"),
+            ..context
+        };
+        reporter.report(&other).await.unwrap();
+
+        let mut names = std::fs::read_dir(&dir_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name.starts_with("rust_") && name.ends_with(".html"))
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names.len(), 3);
+        assert!(names[0].starts_with("rust_digest_2023_11_27_"));
+        assert_eq!(names[2], "rust_index.html");
+
+        let html = std::fs::read_to_string(dir_path.join(&names[0])).unwrap();
+        assert!(html.contains("<mark>Rust</mark> &amp; <mark>CLI</mark>"));
+        assert!(html.contains("&lt;alice&gt; 2023-11-27 12:00:00"));
+        assert!(html.contains("condition_contain: matched"));
+
+        let markdown =
+            std::fs::read_to_string(dir_path.join(names[0].replace(".html", ".md"))).unwrap();
+        assert!(markdown.contains("**Rust** &amp; **CLI** \\*1\\.0\\*"));
+        assert!(markdown.contains("### &lt;alice&gt; 2023-11-27 12:00:00"));
+        assert!(markdown.contains("[index](rust_index.md)"));
+
+        // 新しいものから並び，他のジョブのダイジェストを含まない
+        let index = std::fs::read_to_string(dir_path.join("rust_index.html")).unwrap();
+        let first = index.find(names[1].as_str()).unwrap();
+        let second = index.find(names[0].as_str()).unwrap();
+        assert!(first < second);
+        assert!(!index.contains("index.html\""));
+        assert!(!index.contains("other_"));
+        let other_index = std::fs::read_to_string(dir_path.join("other_index.html")).unwrap();
+        assert!(other_index.contains("other_digest_2023_11_27_"));
+        assert!(!other_index.contains("rust_"));
+
+        std::fs::remove_dir_all(&dir_path).unwrap();
+    }
+}
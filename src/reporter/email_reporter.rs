@@ -0,0 +1,333 @@
+use super::{escape_html, ReportContext, ReportTemplate};
+use crate::Report;
+use crate::{error::Error, Posts};
+
+use lettre::message::{Mailbox, MessageBuilder, MultiPart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// SMTPの接続の暗号化方式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    /// 暗号化しない(ローカルのテスト用)
+    None,
+    /// STARTTLS
+    #[default]
+    StartTls,
+    /// 暗黙のTLS
+    Tls,
+}
+
+/// ポストの一覧をhtmlで作製する．
+fn posts_html(body: &str, posts: &Posts) -> String {
+    let mut html = format!(
+        "<html><body><p>{}</p><table>",
+        escape_html(body).replace('\n', "<br>")
+    );
+
+    for post in posts.iter() {
+        let time = post.time.map(|time| time.to_string()).unwrap_or_default();
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{} {}</td><td>{}</td></tr>",
+            escape_html(&post.author),
+            post.date,
+            time,
+            escape_html(&post.content)
+        ));
+    }
+
+    html.push_str("</table></body></html>");
+    html
+}
+
+/// SMTPでポストのダイジェストをメールするリポーター
+pub struct EmailReporter {
+    host: String,
+    port: Option<u16>,
+    security: SmtpSecurity,
+    credentials: Option<Credentials>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    subject_template: ReportTemplate,
+    body_template: ReportTemplate,
+    failure_subject_template: ReportTemplate,
+    failure_body_template: ReportTemplate,
+}
+
+impl EmailReporter {
+    /// デフォルトの件名のテンプレート
+    pub const DEFAULT_SUBJECT: &'static str =
+        "{% if job_name %}[{{ job_name }}] {% endif %}Search and Report: {{ count }} posts";
+    /// デフォルトの本文のテンプレート
+    pub const DEFAULT_BODY: &'static str =
+        "{{ count }} posts matched the condition.\n\n{{ summary }}";
+    /// 検索の失敗を通知する件名のテンプレート
+    pub const FAILURE_SUBJECT: &'static str =
+        "{% if job_name %}[{{ job_name }}] {% endif %}Search and Report: search failed";
+    /// 検索の失敗を通知する本文のテンプレート
+    pub const FAILURE_BODY: &'static str =
+        "Searching {{ keywords | join(' ') }} on {{ platform }} failed. The page structure may have changed.\n\n{{ error }}";
+
+    pub fn new<S: Into<String>>(host: S, from: &str, to: &[String]) -> Result<Self, Error> {
+        Ok(Self {
+            host: host.into(),
+            port: None,
+            security: SmtpSecurity::default(),
+            credentials: None,
+            from: from.parse()?,
+            to: to
+                .iter()
+                .map(|to| to.parse())
+                .collect::<Result<Vec<Mailbox>, _>>()?,
+            subject_template: ReportTemplate::new(Self::DEFAULT_SUBJECT)?,
+            body_template: ReportTemplate::new(Self::DEFAULT_BODY)?,
+            failure_subject_template: ReportTemplate::new(Self::FAILURE_SUBJECT)?,
+            failure_body_template: ReportTemplate::new(Self::FAILURE_BODY)?,
+        })
+    }
+    /// ポートを設定する．設定しない場合は暗号化方式ごとのデフォルトとなる．
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+    /// 暗号化方式を設定する．
+    pub fn with_security(mut self, security: SmtpSecurity) -> Self {
+        self.security = security;
+        self
+    }
+    /// 認証情報を設定する．
+    pub fn with_credentials<S: Into<String>>(mut self, username: S, password: S) -> Self {
+        self.credentials = Some(Credentials::new(username.into(), password.into()));
+        self
+    }
+    /// 件名のテンプレートを設定する．
+    pub fn with_subject_template(mut self, subject_template: ReportTemplate) -> Self {
+        self.subject_template = subject_template;
+        self
+    }
+    /// 本文のテンプレートを設定する．
+    pub fn with_body_template(mut self, body_template: ReportTemplate) -> Self {
+        self.body_template = body_template;
+        self
+    }
+
+    fn message_builder(&self, subject: String) -> MessageBuilder {
+        let mut builder = Message::builder().from(self.from.clone()).subject(subject);
+        for to in self.to.iter() {
+            builder = builder.to(to.clone());
+        }
+        builder
+    }
+
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>, Error> {
+        let mut builder = match self.security {
+            SmtpSecurity::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.host)
+            }
+            SmtpSecurity::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)?
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host)?,
+        };
+
+        if let Some(port) = self.port {
+            builder = builder.port(port);
+        }
+        if let Some(credentials) = &self.credentials {
+            builder = builder.credentials(credentials.clone());
+        }
+
+        Ok(builder.build())
+    }
+}
+
+#[async_trait::async_trait]
+impl Report for EmailReporter {
+    async fn report(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        let subject = self.subject_template.render(context)?;
+        let body = self.body_template.render(context)?;
+
+        let message =
+            self.message_builder(subject)
+                .multipart(MultiPart::alternative_plain_html(
+                    body.clone(),
+                    posts_html(&body, context.posts),
+                ))?;
+
+        info!("Sending email via {}.", self.host);
+        self.transport()?.send(message).await?;
+
+        Ok(())
+    }
+
+    async fn report_failure(
+        &self,
+        context: &ReportContext<'_>,
+        error: &Error,
+    ) -> Result<(), Error> {
+        let subject = self
+            .failure_subject_template
+            .render_failure(context, error)?;
+        let body = self.failure_body_template.render_failure(context, error)?;
+
+        let message = self.message_builder(subject).body(body)?;
+
+        info!("Sending failure email via {}.", self.host);
+        self.transport()?.send(message).await?;
+
+        Ok(())
+    }
+
+    fn reports_failure(&self) -> bool {
+        true
+    }
+
+    /// タイムアウトしてもメールが送信されている可能性があるため，タイムアウトは再試行しない．
+    fn is_idempotent(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EmailReporter, SmtpSecurity};
+    use crate::reporter::{ReportContext, ReportTemplate};
+    use crate::{error::Error, Post, Report};
+
+    use chrono::NaiveDate;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// 受信したDATAを返すだけのSMTPサーバーを起動する．
+    fn spawn_smtp_sink() -> (u16, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut data = String::new();
+            let mut in_data = false;
+
+            writer.write_all(b"220 localhost\r\n").unwrap();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+                if in_data {
+                    if line == ".\r\n" {
+                        in_data = false;
+                        writer.write_all(b"250 OK\r\n").unwrap();
+                    } else {
+                        data.push_str(&line);
+                    }
+                    continue;
+                }
+                let command = line.to_uppercase();
+                if command.starts_with("DATA") {
+                    in_data = true;
+                    writer.write_all(b"354 Go ahead\r\n").unwrap();
+                } else if command.starts_with("QUIT") {
+                    writer.write_all(b"221 Bye\r\n").unwrap();
+                    break;
+                } else {
+                    writer.write_all(b"250 OK\r\n").unwrap();
+                }
+            }
+            data
+        });
+
+        (port, handle)
+    }
+
+    #[tokio::test]
+    async fn test_email_reporter() {
+        let (port, handle) = spawn_smtp_sink();
+
+        let reporter = EmailReporter::new(
+            "127.0.0.1",
+            "reporter@example.com",
+            &["team@example.com".to_string()],
+        )
+        .unwrap()
+        .with_port(port)
+        .with_security(SmtpSecurity::None)
+        .with_subject_template(
+            ReportTemplate::new("{{ count }} posts about {{ keywords[0] }}").unwrap(),
+        );
+
+        let posts = vec![Post {
+            id: "1".to_string(),
+            author: "alice".to_string(),
+            date: NaiveDate::from_ymd_opt(2023, 11, 27).unwrap(),
+            time: None,
+            content: "<Rust> CLI".to_string(),
+        }];
+
+        let keywords = vec!["Rust".to_string()];
+        let context = ReportContext {
+            keywords: &keywords,
+            ..ReportContext::from_posts(&posts)
+        };
+        reporter.report(&context).await.unwrap();
+
+        let data = handle.join().unwrap();
+        assert!(data.contains("Subject: 1 posts about Rust"));
+        assert!(data.contains("To: team@example.com"));
+        assert!(data.contains("alice: <Rust> CLI"));
+        assert!(data.contains("&lt;Rust&gt; CLI"));
+
+        // 検索の失敗
+        let (port, handle) = spawn_smtp_sink();
+        let no_posts = Vec::new();
+        let context = ReportContext {
+            job_name: Some("rust"),
+            keywords: &keywords,
+            ..ReportContext::from_posts(&no_posts)
+        };
+        reporter
+            .with_port(port)
+            .report_failure(&context, &Error::BlockedPageError("captcha".to_string()))
+            .await
+            .unwrap();
+
+        let data = handle.join().unwrap();
+        assert!(data.contains("Subject: [rust] Search and Report: search failed"));
+        assert!(data.contains("Error::BlockedPageError: captcha"));
+    }
+
+    #[test]
+    fn test_failure_message() {
+        let reporter = EmailReporter::new("127.0.0.1", "reporter@example.com", &[]).unwrap();
+
+        let keywords = vec!["Rust".to_string(), "CLI".to_string()];
+        let no_posts = Vec::new();
+        let context = ReportContext {
+            job_name: Some("rust"),
+            platform: "yahoojp",
+            keywords: &keywords,
+            ..ReportContext::from_posts(&no_posts)
+        };
+        let error = Error::BlockedPageError("captcha".to_string());
+
+        assert_eq!(
+            reporter
+                .failure_subject_template
+                .render_failure(&context, &error)
+                .unwrap(),
+            "[rust] Search and Report: search failed"
+        );
+        assert_eq!(
+            reporter
+                .failure_body_template
+                .render_failure(&context, &error)
+                .unwrap(),
+            "Searching Rust CLI on yahoojp failed. The page structure may have changed.\n\nError::BlockedPageError: captcha"
+        );
+    }
+}
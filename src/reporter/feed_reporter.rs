@@ -0,0 +1,350 @@
+use super::atomic_file::AtomicFile;
+use super::{run_blocking, OwnedReportContext, ReportContext};
+use crate::Report;
+use crate::{error::Error, Clock, Post};
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::info;
+
+/// ジョブの名前が無い場合のフィードのタイトル
+const DEFAULT_TITLE: &str = "Search and Report";
+
+/// エントリーのタイトルに含める内容の文字数
+const TITLE_CONTENT_LENGTH: usize = 40;
+
+/// フィードの形式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedFormat {
+    /// Atom 1.0
+    #[default]
+    Atom,
+    /// RSS 2.0
+    Rss,
+}
+
+/// 形式によらないフィードのエントリー
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FeedEntry {
+    id: String,
+    title: String,
+    author: String,
+    updated: DateTime<FixedOffset>,
+    content: String,
+}
+
+impl FeedEntry {
+    fn from_post(platform: &str, post: &Post, now: DateTime<FixedOffset>) -> Self {
+        let content_head = post
+            .content
+            .chars()
+            .take(TITLE_CONTENT_LENGTH)
+            .collect::<String>();
+
+        Self {
+            id: format!("urn:search-and-report:{}:{}", platform, post.id),
+            title: format!("{}: {}", post.author, content_head),
+            author: post.author.clone(),
+            // ポストの日時はローカル時間であり，時間が不明な場合は日付のみを用いる
+            updated: NaiveDateTime::new(post.date, post.time.unwrap_or(NaiveTime::MIN))
+                .and_local_timezone(Local)
+                .earliest()
+                .map(|datetime| datetime.fixed_offset())
+                .unwrap_or(now),
+            content: post.content.clone(),
+        }
+    }
+
+    fn from_atom(entry: &atom_syndication::Entry) -> Self {
+        Self {
+            id: entry.id.clone(),
+            title: entry.title.value.clone(),
+            author: entry
+                .authors
+                .first()
+                .map(|person| person.name.clone())
+                .unwrap_or_default(),
+            updated: entry.updated,
+            content: entry
+                .content
+                .as_ref()
+                .and_then(|content| content.value.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn to_atom(&self) -> atom_syndication::Entry {
+        atom_syndication::Entry {
+            id: self.id.clone(),
+            title: atom_syndication::Text::plain(self.title.clone()),
+            authors: vec![atom_syndication::Person {
+                name: self.author.clone(),
+                ..Default::default()
+            }],
+            updated: self.updated,
+            content: Some(atom_syndication::Content {
+                value: Some(self.content.clone()),
+                content_type: Some("text".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn from_rss(item: &rss::Item) -> Option<Self> {
+        Some(Self {
+            id: item.guid.as_ref()?.value.clone(),
+            title: item.title.clone().unwrap_or_default(),
+            author: item.author.clone().unwrap_or_default(),
+            updated: DateTime::parse_from_rfc2822(item.pub_date.as_deref()?).ok()?,
+            content: item.description.clone().unwrap_or_default(),
+        })
+    }
+
+    fn to_rss(&self) -> rss::Item {
+        rss::Item {
+            guid: Some(rss::Guid {
+                value: self.id.clone(),
+                permalink: false,
+            }),
+            title: Some(self.title.clone()),
+            author: Some(self.author.clone()),
+            pub_date: Some(self.updated.to_rfc2822()),
+            description: Some(self.content.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+/// ポストの最新のN件を含むフィードのファイルを更新するリポーター．
+#[derive(Clone)]
+pub struct FeedReporter {
+    path: PathBuf,
+    clock: Arc<dyn Clock>,
+    format: FeedFormat,
+    max_entries: usize,
+    link: Option<String>,
+}
+
+impl FeedReporter {
+    /// デフォルトのエントリー数の上限
+    pub const DEFAULT_MAX_ENTRIES: usize = 50;
+
+    pub fn new<P: Into<PathBuf>>(path: P, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            path: path.into(),
+            clock,
+            format: FeedFormat::default(),
+            max_entries: Self::DEFAULT_MAX_ENTRIES,
+            link: None,
+        }
+    }
+    /// フィードの形式を設定する．
+    pub fn with_format(mut self, format: FeedFormat) -> Self {
+        self.format = format;
+        self
+    }
+    /// エントリー数の上限を設定する．
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+    /// フィードのリンク先を設定する．
+    pub fn with_link<S: Into<String>>(mut self, link: S) -> Self {
+        self.link = Some(link.into());
+        self
+    }
+
+    /// 既存のフィードのエントリーを読み込む．
+    fn read_entries(&self) -> Result<Vec<FeedEntry>, Error> {
+        if !self.path.is_file() {
+            return Ok(Vec::new());
+        }
+
+        let reader = BufReader::new(File::open(&self.path)?);
+        let entries = match self.format {
+            FeedFormat::Atom => atom_syndication::Feed::read_from(reader)?
+                .entries
+                .iter()
+                .map(FeedEntry::from_atom)
+                .collect(),
+            FeedFormat::Rss => rss::Channel::read_from(reader)?
+                .items
+                .iter()
+                .filter_map(FeedEntry::from_rss)
+                .collect(),
+        };
+        Ok(entries)
+    }
+
+    /// フィードを書き込む．
+    fn write_entries(
+        &self,
+        title: &str,
+        id: &str,
+        entries: &[FeedEntry],
+        now: DateTime<FixedOffset>,
+    ) -> Result<(), Error> {
+        let file = AtomicFile::create(&self.path, false)?;
+        let file = match self.format {
+            FeedFormat::Atom => {
+                let feed = atom_syndication::Feed {
+                    title: atom_syndication::Text::plain(title),
+                    id: id.to_string(),
+                    updated: now,
+                    links: self
+                        .link
+                        .iter()
+                        .map(|link| atom_syndication::Link {
+                            href: link.clone(),
+                            ..Default::default()
+                        })
+                        .collect(),
+                    entries: entries.iter().map(FeedEntry::to_atom).collect(),
+                    ..Default::default()
+                };
+                feed.write_to(file)?
+            }
+            FeedFormat::Rss => {
+                let channel = rss::Channel {
+                    title: title.to_string(),
+                    link: self.link.clone().unwrap_or_default(),
+                    description: title.to_string(),
+                    last_build_date: Some(now.to_rfc2822()),
+                    items: entries.iter().map(FeedEntry::to_rss).collect(),
+                    ..Default::default()
+                };
+                channel.write_to(file)?
+            }
+        };
+        file.commit()
+    }
+}
+
+impl FeedReporter {
+    /// フィードを更新する．
+    fn update(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        if let Some(dir_path) = self.path.parent() {
+            if !dir_path.as_os_str().is_empty() && !dir_path.is_dir() {
+                std::fs::DirBuilder::new()
+                    .recursive(true)
+                    .create(dir_path)?;
+            }
+        }
+
+        let now = self.clock.now_local().fixed_offset();
+        let mut entries = context
+            .posts
+            .iter()
+            .map(|post| FeedEntry::from_post(context.platform, post, now))
+            .collect::<Vec<_>>();
+
+        // 同じポストは新しいものに置き換える
+        for entry in self.read_entries()? {
+            if entries.iter().all(|new_entry| new_entry.id != entry.id) {
+                entries.push(entry);
+            }
+        }
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.updated));
+        entries.truncate(self.max_entries);
+
+        let title = context.job_name.unwrap_or(DEFAULT_TITLE);
+        let id = format!("urn:search-and-report:feed:{}", title);
+
+        info!("Updating feed: {:?}", self.path);
+        self.write_entries(title, &id, &entries, now)
+    }
+}
+
+#[async_trait::async_trait]
+impl Report for FeedReporter {
+    async fn report(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        let reporter = self.clone();
+        let context = OwnedReportContext::new(context);
+        run_blocking(move || reporter.update(&context.as_context())).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FeedFormat, FeedReporter};
+    use crate::clock::FixedClock;
+    use crate::reporter::ReportContext;
+    use crate::{Post, Report};
+
+    use chrono::{Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+    use std::io::BufReader;
+    use std::sync::Arc;
+
+    fn post(id: &str, hour: u32, content: &str) -> Post {
+        Post {
+            id: id.to_string(),
+            author: "alice".to_string(),
+            date: NaiveDate::from_ymd_opt(2023, 11, 27).unwrap(),
+            time: Some(NaiveTime::from_hms_opt(hour, 0, 0).unwrap()),
+            content: content.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_feed_reporter() {
+        let dir_path =
+            std::env::temp_dir().join(format!("search_and_report_feed_{}", std::process::id()));
+        let clock = Arc::new(FixedClock::new(
+            Utc.with_ymd_and_hms(2023, 11, 27, 3, 0, 0).unwrap(),
+        ));
+
+        for format in [FeedFormat::Atom, FeedFormat::Rss] {
+            let path = dir_path.join(format!("rust.{:?}.xml", format));
+            let reporter = FeedReporter::new(&path, clock.clone())
+                .with_format(format)
+                .with_max_entries(2);
+
+            let posts = vec![post("1", 9, "Rust CLI"), post("2", 10, "Rust TUI")];
+            let context = ReportContext {
+                job_name: Some("rust"),
+                platform: "yahoojp",
+                ..ReportContext::from_posts(&posts)
+            };
+            reporter.report(&context).await.unwrap();
+
+            // 既存のエントリーを更新し，古いものから溢れる
+            clock.advance(Duration::hours(1));
+            let posts = vec![
+                post("2", 10, "Rust TUI (edited)"),
+                post("3", 11, "Rust GUI"),
+            ];
+            reporter
+                .report(&ReportContext {
+                    posts: &posts,
+                    ..context
+                })
+                .await
+                .unwrap();
+
+            let reader = BufReader::new(std::fs::File::open(&path).unwrap());
+            let contents = match format {
+                FeedFormat::Atom => atom_syndication::Feed::read_from(reader)
+                    .unwrap()
+                    .entries
+                    .iter()
+                    .map(|entry| entry.content.clone().unwrap().value.unwrap())
+                    .collect::<Vec<_>>(),
+                FeedFormat::Rss => rss::Channel::read_from(reader)
+                    .unwrap()
+                    .items
+                    .iter()
+                    .map(|item| item.description.clone().unwrap())
+                    .collect::<Vec<_>>(),
+            };
+            assert_eq!(contents, vec!["Rust GUI", "Rust TUI (edited)"]);
+        }
+
+        std::fs::remove_dir_all(&dir_path).unwrap();
+    }
+}
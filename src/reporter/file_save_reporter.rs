@@ -0,0 +1,430 @@
+use super::atomic_file::AtomicFile;
+use super::retention::SavedFilePattern;
+use super::{run_blocking, OwnedReportContext, ReportContext, ReportTemplate, RetentionPolicy};
+use crate::Report;
+use crate::{error::Error, Clock, Posts};
+
+use chrono::format::{Item, StrftimeItems};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use parquet::basic::Compression as ParquetCompression;
+use parquet::data_type::{ByteArray, ByteArrayType, Int32Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::info;
+
+/// 保存するファイルの形式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SaveFormat {
+    /// ポストの配列を整形したjson
+    #[default]
+    Json,
+    /// 一行に一つのポスト．同名のファイルが存在する場合は追記する．
+    Jsonl,
+    /// ヘッダー付きのcsv．同名のファイルが存在する場合はヘッダーを除いて追記する．
+    Csv,
+    /// Apache Parquet
+    Parquet,
+}
+
+impl SaveFormat {
+    /// ファイルの拡張子
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SaveFormat::Json => "json",
+            SaveFormat::Jsonl => "jsonl",
+            SaveFormat::Csv => "csv",
+            SaveFormat::Parquet => "parquet",
+        }
+    }
+
+    /// 追記するかどうか
+    fn appends(&self) -> bool {
+        matches!(self, SaveFormat::Jsonl | SaveFormat::Csv)
+    }
+}
+
+/// Parquetのスキーマ．日付はUNIXエポックからの日数とする．
+const PARQUET_SCHEMA: &str = "
+message post {
+    REQUIRED BYTE_ARRAY id (UTF8);
+    REQUIRED BYTE_ARRAY author (UTF8);
+    REQUIRED INT32 date (DATE);
+    OPTIONAL BYTE_ARRAY time (UTF8);
+    REQUIRED BYTE_ARRAY content (UTF8);
+}
+";
+
+/// ポストをParquetに変換する．
+fn posts_parquet(posts: &Posts, gzip: bool) -> Result<Vec<u8>, Error> {
+    let schema = Arc::new(parse_message_type(PARQUET_SCHEMA)?);
+    let properties = WriterProperties::builder()
+        .set_compression(if gzip {
+            ParquetCompression::GZIP(Default::default())
+        } else {
+            ParquetCompression::UNCOMPRESSED
+        })
+        .build();
+
+    let string_column = |f: fn(&crate::Post) -> &str| {
+        posts
+            .iter()
+            .map(|post| ByteArray::from(f(post)))
+            .collect::<Vec<_>>()
+    };
+    let epoch = chrono::NaiveDate::default();
+    let dates = posts
+        .iter()
+        .map(|post| (post.date - epoch).num_days() as i32)
+        .collect::<Vec<_>>();
+    let times = posts
+        .iter()
+        .filter_map(|post| {
+            post.time
+                .map(|time| ByteArray::from(time.to_string().as_str()))
+        })
+        .collect::<Vec<_>>();
+    let time_levels = posts
+        .iter()
+        .map(|post| post.time.is_some() as i16)
+        .collect::<Vec<_>>();
+
+    let mut buffer = Vec::new();
+    let mut writer = SerializedFileWriter::new(&mut buffer, schema, Arc::new(properties))?;
+    let mut row_group = writer.next_row_group()?;
+    let mut index = 0;
+    while let Some(mut column) = row_group.next_column()? {
+        match index {
+            0 => column.typed::<ByteArrayType>().write_batch(
+                &string_column(|post| &post.id),
+                None,
+                None,
+            )?,
+            1 => column.typed::<ByteArrayType>().write_batch(
+                &string_column(|post| &post.author),
+                None,
+                None,
+            )?,
+            2 => column
+                .typed::<Int32Type>()
+                .write_batch(&dates, None, None)?,
+            3 => column
+                .typed::<ByteArrayType>()
+                .write_batch(&times, Some(&time_levels), None)?,
+            _ => column.typed::<ByteArrayType>().write_batch(
+                &string_column(|post| &post.content),
+                None,
+                None,
+            )?,
+        };
+        column.close()?;
+        index += 1;
+    }
+    row_group.close()?;
+    writer.close()?;
+
+    Ok(buffer)
+}
+
+/// ポストを形式に従って変換する．Parquetの場合は`gzip`を内部の圧縮方式として用いる．
+fn encode_posts(
+    format: SaveFormat,
+    posts: &Posts,
+    with_header: bool,
+    gzip: bool,
+) -> Result<Vec<u8>, Error> {
+    match format {
+        SaveFormat::Json => Ok(serde_json::to_vec_pretty(posts)?),
+        SaveFormat::Jsonl => {
+            let mut buffer = Vec::new();
+            for post in posts.iter() {
+                serde_json::to_writer(&mut buffer, post)?;
+                buffer.push(b'\n');
+            }
+            Ok(buffer)
+        }
+        SaveFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(with_header)
+                .from_writer(Vec::new());
+            for post in posts.iter() {
+                writer.serialize(post)?;
+            }
+            writer
+                .into_inner()
+                .map_err(|e| Error::FileError(e.to_string()))
+        }
+        SaveFormat::Parquet => posts_parquet(posts, gzip),
+    }
+}
+
+/// Postsを指定した形式でファイルに保存するリポーター．
+#[derive(Clone)]
+pub struct FileSaveReporter {
+    dir_path: PathBuf,
+    clock: Arc<dyn Clock>,
+    format: SaveFormat,
+    filename_template: ReportTemplate,
+    gzip: bool,
+    retention: RetentionPolicy,
+}
+
+impl FileSaveReporter {
+    /// デフォルトのファイル名のテンプレート(拡張子を除く)
+    pub const DEFAULT_FILENAME: &'static str =
+        "{% if job_name %}{{ job_name | filename }}_{% endif %}report_%Y_%m_%d_%H_%M_%S";
+
+    pub fn new<P: Into<PathBuf>>(dir_path: P, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            dir_path: dir_path.into(),
+            clock,
+            format: SaveFormat::default(),
+            filename_template: ReportTemplate::new_filename(Self::DEFAULT_FILENAME)
+                .expect("default template is valid"),
+            gzip: false,
+            retention: RetentionPolicy::default(),
+        }
+    }
+    /// 保存する形式を設定する．
+    pub fn with_format(mut self, format: SaveFormat) -> Self {
+        self.format = format;
+        self
+    }
+    /// 拡張子を除いたファイル名のテンプレートを設定する．テンプレートを描画した後，
+    /// `%Y`などの書式をローカルの現在時刻で置き換える．値がファイル名として扱われるように，
+    /// テンプレートは`ReportTemplate::new_filename`でコンパイルする．
+    pub fn with_filename_template(mut self, filename_template: ReportTemplate) -> Self {
+        self.filename_template = filename_template;
+        self
+    }
+    /// gzipで圧縮するかどうかを設定する．
+    pub fn with_gzip(mut self, gzip: bool) -> Self {
+        self.gzip = gzip;
+        self
+    }
+    /// 保存したファイルの保持方針を設定する．
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// 保存するファイルの拡張子
+    fn extension(&self) -> String {
+        if self.gzip && self.format != SaveFormat::Parquet {
+            format!("{}.gz", self.format.extension())
+        } else {
+            self.format.extension().to_string()
+        }
+    }
+
+    /// 保存先のファイルのパスと，保存したファイルの名前のパターン
+    fn file_path(&self, context: &ReportContext<'_>) -> Result<(PathBuf, SavedFilePattern), Error> {
+        let rendered = self.filename_template.render(context)?;
+        if rendered.contains(['/', '\\']) {
+            return Err(Error::TemplateError(format!(
+                "Path separator in filename: {}",
+                rendered
+            )));
+        }
+        let items = StrftimeItems::new(&rendered).collect::<Vec<_>>();
+        if items.iter().any(|item| matches!(item, Item::Error)) {
+            return Err(Error::TemplateError(format!(
+                "Invalid datetime format in filename: {}",
+                rendered
+            )));
+        }
+        let now = self.clock.now_local();
+        let extension = self.extension();
+
+        let file_name = format!("{}.{}", now.format_with_items(items.into_iter()), extension);
+
+        Ok((
+            self.dir_path.join(file_name),
+            SavedFilePattern::new(&rendered, &extension),
+        ))
+    }
+}
+
+impl FileSaveReporter {
+    /// 形式に従ってファイルに保存し，保持方針を適用する．
+    fn save(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        // ディレクトリの存在確認，作製
+        if !self.dir_path.is_dir() {
+            std::fs::DirBuilder::new()
+                .recursive(true)
+                .create(&self.dir_path)?
+        }
+
+        let (file_path, pattern) = self.file_path(context)?;
+        let append = self.format.appends() && file_path.is_file();
+
+        let data = encode_posts(self.format, context.posts, !append, self.gzip)?;
+
+        info!("Saving into: {:?}", file_path);
+        // 追記する場合も既存の内容を写した一時ファイルに書き込んでから置き換える
+        let mut file = AtomicFile::create(&file_path, append)?;
+
+        // 追記したgzipは複数のメンバーからなる一つのgzipとして読める
+        if self.gzip && self.format != SaveFormat::Parquet {
+            let mut encoder = GzEncoder::new(file, Compression::default());
+            encoder.write_all(&data)?;
+            encoder.finish()?.commit()?;
+        } else {
+            file.write_all(&data)?;
+            file.commit()?;
+        }
+
+        self.retention
+            .apply(&self.dir_path, &pattern, self.clock.now_local())?;
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Report for FileSaveReporter {
+    async fn report(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        let reporter = self.clone();
+        let context = OwnedReportContext::new(context);
+        run_blocking(move || reporter.save(&context.as_context())).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FileSaveReporter, SaveFormat};
+    use crate::clock::FixedClock;
+    use crate::reporter::{ReportContext, ReportTemplate};
+    use crate::{Clock, Post, Report};
+
+    use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+    use flate2::read::MultiGzDecoder;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use std::io::Read;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_file_save_reporter() {
+        let dir_path = std::env::temp_dir().join(format!(
+            "search_and_report_file_save_{}",
+            std::process::id()
+        ));
+        let clock = Arc::new(FixedClock::new(
+            Utc.with_ymd_and_hms(2023, 11, 27, 3, 0, 0).unwrap(),
+        ));
+        let posts = vec![
+            Post {
+                id: "1".to_string(),
+                author: "alice".to_string(),
+                date: NaiveDate::from_ymd_opt(2023, 11, 27).unwrap(),
+                time: Some(NaiveTime::from_hms_opt(12, 0, 0).unwrap()),
+                content: "Rust, CLI".to_string(),
+            },
+            Post {
+                id: "2".to_string(),
+                author: "bob".to_string(),
+                date: NaiveDate::from_ymd_opt(2023, 11, 26).unwrap(),
+                time: None,
+                content: "Rust TUI".to_string(),
+            },
+        ];
+        let context = ReportContext {
+            job_name: Some("rust"),
+            ..ReportContext::from_posts(&posts)
+        };
+        let template = ReportTemplate::new_filename("{{ job_name | filename }}_%Y%m%d").unwrap();
+
+        // jsonlはgzipのまま追記される
+        let reporter = FileSaveReporter::new(&dir_path, clock.clone())
+            .with_format(SaveFormat::Jsonl)
+            .with_filename_template(template.clone())
+            .with_gzip(true);
+        reporter.report(&context).await.unwrap();
+        reporter.report(&context).await.unwrap();
+
+        let file_name = format!("rust_{}.jsonl.gz", clock.now_local().format("%Y%m%d"));
+        let mut jsonl = String::new();
+        MultiGzDecoder::new(std::fs::File::open(dir_path.join(file_name)).unwrap())
+            .read_to_string(&mut jsonl)
+            .unwrap();
+        assert_eq!(jsonl.lines().count(), 4);
+        assert!(jsonl
+            .starts_with(r#"{"id":"1","author":"alice","date":"2023-11-27","time":"12:00:00""#));
+
+        // csvのヘッダーは最初のみ
+        let reporter = FileSaveReporter::new(&dir_path, clock.clone())
+            .with_format(SaveFormat::Csv)
+            .with_filename_template(template.clone());
+        reporter.report(&context).await.unwrap();
+        reporter.report(&context).await.unwrap();
+
+        let file_name = format!("rust_{}.csv", clock.now_local().format("%Y%m%d"));
+        let csv = std::fs::read_to_string(dir_path.join(file_name)).unwrap();
+        assert_eq!(
+            csv.lines().collect::<Vec<_>>(),
+            vec![
+                "id,author,date,time,content",
+                r#"1,alice,2023-11-27,12:00:00,"Rust, CLI""#,
+                "2,bob,2023-11-26,,Rust TUI",
+                r#"1,alice,2023-11-27,12:00:00,"Rust, CLI""#,
+                "2,bob,2023-11-26,,Rust TUI",
+            ]
+        );
+
+        let reporter = FileSaveReporter::new(&dir_path, clock.clone())
+            .with_format(SaveFormat::Parquet)
+            .with_filename_template(template)
+            .with_gzip(true);
+        reporter.report(&context).await.unwrap();
+
+        let file_name = format!("rust_{}.parquet", clock.now_local().format("%Y%m%d"));
+        let reader =
+            SerializedFileReader::new(std::fs::File::open(dir_path.join(file_name)).unwrap())
+                .unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+
+        // ジョブの名前の`%`や`/`は書式やパスの区切りとして扱わない
+        let context = ReportContext {
+            job_name: Some("../rust 100%"),
+            ..context
+        };
+        let reporter = FileSaveReporter::new(&dir_path, clock.clone())
+            .with_filename_template(ReportTemplate::new_filename("{{ job_name }}_%Y").unwrap());
+        reporter.report(&context).await.unwrap();
+        let reporter = FileSaveReporter::new(&dir_path, clock.clone());
+        reporter.report(&context).await.unwrap();
+        let mut file_names = std::fs::read_dir(&dir_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|file_name| file_name.contains("rust_100"))
+            .collect::<Vec<_>>();
+        file_names.sort();
+        assert_eq!(
+            file_names,
+            vec![
+                format!("{}.json", clock.now_local().format(".._rust_100__%Y")),
+                format!(
+                    "{}.json",
+                    clock
+                        .now_local()
+                        .format(".._rust_100__report_%Y_%m_%d_%H_%M_%S")
+                ),
+            ]
+        );
+
+        let reporter = FileSaveReporter::new(&dir_path, clock.clone())
+            .with_filename_template(ReportTemplate::new_filename("%Q").unwrap());
+        assert!(reporter.report(&context).await.is_err());
+        let reporter = FileSaveReporter::new(&dir_path, clock)
+            .with_filename_template(ReportTemplate::new_filename("../%Y").unwrap());
+        assert!(reporter.report(&context).await.is_err());
+
+        std::fs::remove_dir_all(&dir_path).unwrap();
+    }
+}
@@ -1,35 +1,43 @@
-use chrono::Timelike;
-
+use super::atomic_file::write_atomic;
+use super::retention::SavedFilePattern;
+use super::{file_name_safe, run_blocking, OwnedReportContext, ReportContext, RetentionPolicy};
 use crate::Report;
-use crate::{error::Error, Posts};
+use crate::{error::Error, Clock};
 
 use std::borrow::Cow;
-use std::fs::File;
-use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tracing::info;
 
 /// Postsの内容を全てjsonに保存するリポーター．
+#[derive(Clone)]
 pub struct JsonSaveReporter {
     dir_path: PathBuf,
+    clock: Arc<dyn Clock>,
+    retention: RetentionPolicy,
 }
 
 impl JsonSaveReporter {
-    pub fn new<'a, P: Into<Cow<'a, Path>>>(dir_path: P) -> Self {
+    pub fn new<'a, P: Into<Cow<'a, Path>>>(dir_path: P, clock: Arc<dyn Clock>) -> Self {
         let dir_path: Cow<'a, Path> = dir_path.into();
 
         Self {
             dir_path: dir_path.into_owned(),
+            clock,
+            retention: RetentionPolicy::default(),
         }
     }
+    /// 保存したファイルの保持方針を設定する．
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
 }
 
-#[async_trait::async_trait]
-impl Report for JsonSaveReporter {
-    async fn report(&self, posts: &Posts) -> Result<(), Error> {
-        use chrono::Datelike;
-
-        let json_string = serde_json::to_string_pretty(posts)?;
+impl JsonSaveReporter {
+    /// jsonに保存する．
+    fn save(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        let json_string = serde_json::to_string_pretty(context.posts)?;
 
         // ディレクトリの存在確認，作製
         if !self.dir_path.is_dir() {
@@ -38,27 +46,37 @@ impl Report for JsonSaveReporter {
                 .create(&self.dir_path)?
         }
 
-        let file_path = {
-            let now = chrono::Local::now();
-            let file_name = format!(
-                "report_{:4}_{:02}_{:02}_{:02}_{:02}_{:02.0}.json",
-                now.year(),
-                now.month(),
-                now.day(),
-                now.hour(),
-                now.minute(),
-                now.second()
-            );
-
-            let mut file_path = self.dir_path.clone();
-            file_path.push(file_name);
-            file_path
-        };
+        // `file_name_safe`は`%`を含まないため，ジョブ名がそのまま書式として解釈されることはない
+        let file_stem = format!(
+            "{}report_%Y_%m_%d_%H_%M_%S",
+            context
+                .job_name
+                .map(|name| format!("{}_", file_name_safe(name)))
+                .unwrap_or_default()
+        );
+        let file_path = self.dir_path.join(format!(
+            "{}.json",
+            self.clock.now_local().format(&file_stem)
+        ));
 
         info!("Creating and saving into: {:?}", file_path);
-        let mut file = File::create(&file_path)?;
-        file.write_all(json_string.as_bytes())?;
+        write_atomic(&file_path, json_string)?;
+
+        self.retention.apply(
+            &self.dir_path,
+            &SavedFilePattern::new(&file_stem, "json"),
+            self.clock.now_local(),
+        )?;
 
         Ok(())
     }
 }
+
+#[async_trait::async_trait]
+impl Report for JsonSaveReporter {
+    async fn report(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        let reporter = self.clone();
+        let context = OwnedReportContext::new(context);
+        run_blocking(move || reporter.save(&context.as_context())).await
+    }
+}
@@ -1,64 +1,118 @@
+use super::{latest_post, run_blocking, ReportContext, ReportTemplate};
+use crate::error::Error;
 use crate::Report;
-use crate::{error::Error, Posts};
 
-use chrono::NaiveDateTime;
 use notify_rust::Notification;
 
+/// 通知を表示する．D-Busなどの呼び出しはブロッキングするため別スレッドで行う．
+async fn show(notification: Notification) -> Result<(), Error> {
+    run_blocking(move || {
+        notification.show()?;
+        Ok(())
+    })
+    .await
+}
+
 /// Osの通知を用いたリポーター
 pub struct StaticNotificationReporter {
-    content: String,
+    summary: ReportTemplate,
+    content: ReportTemplate,
 }
 
 impl StaticNotificationReporter {
-    pub fn new<S: Into<String>>(content: S) -> Self {
+    /// デフォルトの通知のタイトル
+    pub const DEFAULT_SUMMARY: &'static str =
+        "{% if job_name %}{{ job_name }}: {% endif %}Search and Reporter Notification";
+
+    pub fn new(content: ReportTemplate) -> Self {
         Self {
-            content: content.into(),
+            summary: ReportTemplate::new(Self::DEFAULT_SUMMARY).unwrap(),
+            content,
         }
     }
+    /// 通知のタイトルのテンプレートを設定する．
+    pub fn with_summary(mut self, summary: ReportTemplate) -> Self {
+        self.summary = summary;
+        self
+    }
 }
 
 #[async_trait::async_trait]
 impl Report for StaticNotificationReporter {
-    async fn report(&self, _: &Posts) -> Result<(), Error> {
-        Notification::new()
-            .summary("Search and Reporter Notification")
-            .body(&self.content)
-            .show()?;
+    async fn report(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        let mut notification = Notification::new();
+        notification
+            .summary(&self.summary.render(context)?)
+            .body(&self.content.render(context)?);
 
-        Ok(())
+        show(notification).await
+    }
+
+    async fn report_failure(
+        &self,
+        context: &ReportContext<'_>,
+        error: &Error,
+    ) -> Result<(), Error> {
+        let summary = match context.job_name {
+            Some(job_name) => format!("{}: Search and Reporter Alert", job_name),
+            None => "Search and Reporter Alert".to_string(),
+        };
+        let mut notification = Notification::new();
+        notification.summary(&summary).body(&error.to_string());
+
+        show(notification).await
+    }
+
+    fn reports_failure(&self) -> bool {
+        true
     }
 }
 
 /// 通知で最新ポストの内容を表示するリポーター
-pub struct LatestPostNotificationReporter;
+pub struct LatestPostNotificationReporter {
+    summary: ReportTemplate,
+    content: ReportTemplate,
+}
+
+impl LatestPostNotificationReporter {
+    /// デフォルトの通知のタイトル
+    pub const DEFAULT_SUMMARY: &'static str =
+        "{% if job_name %}{{ job_name }}: {% endif %}Latest Post Notification";
+    /// デフォルトの通知の内容
+    pub const DEFAULT_CONTENT: &'static str =
+        "{{ latest_author }}: {{ latest_datetime }}\n{{ latest_content }}";
+
+    /// 通知のタイトルのテンプレートを設定する．
+    pub fn with_summary(mut self, summary: ReportTemplate) -> Self {
+        self.summary = summary;
+        self
+    }
+    /// 通知の内容のテンプレートを設定する．
+    pub fn with_content(mut self, content: ReportTemplate) -> Self {
+        self.content = content;
+        self
+    }
+}
+
+impl Default for LatestPostNotificationReporter {
+    fn default() -> Self {
+        Self {
+            summary: ReportTemplate::new(Self::DEFAULT_SUMMARY).unwrap(),
+            content: ReportTemplate::new(Self::DEFAULT_CONTENT).unwrap(),
+        }
+    }
+}
 
 #[async_trait::async_trait]
 impl Report for LatestPostNotificationReporter {
-    async fn report(&self, posts: &Posts) -> Result<(), Error> {
-        if let Some((latest_post_index, latest_datetime)) = posts
-            .iter()
-            .enumerate()
-            .filter_map(|(i, post)| {
-                post.time
-                    .map(|time| (i, NaiveDateTime::new(post.date, time)))
-            })
-            .max_by(|(_, x), (_, y)| x.cmp(y))
-        {
-            let latest_post = posts.get(latest_post_index).unwrap(); // 存在は確定されているため
-            let content = format!(
-                r#"
-{}: {}
-{}
-            "#,
-                &latest_post.author, latest_datetime, &latest_post.content
-            );
-
-            Notification::new()
-                .summary("Latest Post Notification")
-                .body(&content)
-                .show()?;
-
-            Ok(())
+    async fn report(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        if latest_post(context.posts).is_some() {
+            let mut notification = Notification::new();
+            notification
+                .summary(&self.summary.render(context)?)
+                .body(&self.content.render(context)?);
+
+            show(notification).await
         } else {
             Err(Error::NothingPostError)
         }
@@ -0,0 +1,338 @@
+use super::atomic_file::AtomicFile;
+use crate::error::Error;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// 日ごとにまとめたアーカイブのファイル名の接頭辞
+const ARCHIVE_PREFIX: &str = "archive_";
+
+/// 保存したファイルの保持方針．リポートの度に適用される．
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// 更新から指定した日数を過ぎたファイルを削除する．
+    pub max_age_days: Option<u64>,
+    /// ファイル数の上限．超えた場合は古いものから削除する．
+    pub max_files: Option<usize>,
+    /// 合計サイズ(バイト)の上限．超えた場合は古いものから削除する．
+    pub max_total_bytes: Option<u64>,
+    /// 前日以前のファイルを日ごとに`<ファイル名の接頭辞>archive_YYYY_MM_DD.tar.gz`にまとめる．
+    pub daily_rollup: bool,
+}
+
+/// リポーターが保存したファイルの名前のパターン．同じディレクトリの他のファイルを
+/// 削除しないよう，保持方針はこれに一致するファイルにのみ適用する．
+#[derive(Debug, Clone)]
+pub(crate) struct SavedFilePattern {
+    /// 保存したファイルの名前
+    name: Regex,
+    /// アーカイブの名前の接頭辞
+    archive_prefix: String,
+    /// アーカイブの名前
+    archive: Regex,
+}
+
+impl SavedFilePattern {
+    /// `%Y`などの書式を含む拡張子を除いたファイル名と拡張子から作製する．
+    /// アーカイブの名前にはファイル名の書式より前の部分を接頭辞として付ける．
+    pub(crate) fn new(file_stem: &str, extension: &str) -> Self {
+        let mut name = String::from("^");
+        let mut literal_prefix = String::new();
+        let mut in_prefix = true;
+        for item in StrftimeItems::new(file_stem) {
+            let literal = match item {
+                Item::Literal(s) | Item::Space(s) => Some(s.to_string()),
+                Item::OwnedLiteral(ref s) | Item::OwnedSpace(ref s) => Some(s.to_string()),
+                _ => None,
+            };
+            match literal {
+                Some(literal) => {
+                    name.push_str(&regex::escape(&literal));
+                    if in_prefix {
+                        literal_prefix.push_str(&literal);
+                    }
+                }
+                None => {
+                    name.push_str(match item {
+                        Item::Numeric(..) => r"\s*-?\d+",
+                        _ => ".+?",
+                    });
+                    in_prefix = false;
+                }
+            }
+        }
+        name.push_str(&format!(r"\.{}$", regex::escape(extension)));
+
+        let archive_prefix = format!("{}{}", literal_prefix, ARCHIVE_PREFIX);
+        let archive = format!(
+            r"^{}\d{{4}}_\d{{2}}_\d{{2}}(_\d+)?\.tar\.gz$",
+            regex::escape(&archive_prefix)
+        );
+
+        Self {
+            name: Regex::new(&name).expect("escaped pattern is valid"),
+            archive_prefix,
+            archive: Regex::new(&archive).expect("escaped pattern is valid"),
+        }
+    }
+}
+
+/// ディレクトリ内の保存したファイル
+struct SavedFile {
+    path: PathBuf,
+    modified: DateTime<Local>,
+    size: u64,
+    is_archive: bool,
+}
+
+/// パターンに一致する保存したファイルを更新日時の古い順に取得する．
+fn saved_files(dir_path: &Path, pattern: &SavedFilePattern) -> Result<Vec<SavedFile>, Error> {
+    let mut files = Vec::new();
+
+    for entry in std::fs::read_dir(dir_path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        let is_archive = pattern.archive.is_match(&name);
+
+        if metadata.is_file() && (is_archive || pattern.name.is_match(&name)) {
+            files.push(SavedFile {
+                path: entry.path(),
+                modified: metadata.modified()?.into(),
+                size: metadata.len(),
+                is_archive,
+            });
+        }
+    }
+
+    files.sort_by_key(|file| file.modified);
+    Ok(files)
+}
+
+/// 既存のアーカイブと重複しないパス
+fn archive_path(dir_path: &Path, archive_prefix: &str, date: NaiveDate) -> PathBuf {
+    let stem = format!(
+        "{}{:4}_{:02}_{:02}",
+        archive_prefix,
+        date.year(),
+        date.month(),
+        date.day()
+    );
+
+    let mut path = dir_path.join(format!("{}.tar.gz", stem));
+    let mut count = 1;
+    while path.exists() {
+        count += 1;
+        path = dir_path.join(format!("{}_{}.tar.gz", stem, count));
+    }
+    path
+}
+
+/// ファイルをまとめたアーカイブを作製し，元のファイルを削除する．
+fn archive(archive_path: &Path, files: &[SavedFile]) -> Result<(), Error> {
+    let archive_file = AtomicFile::create(archive_path, false)?;
+    let mut builder = tar::Builder::new(GzEncoder::new(archive_file, Compression::default()));
+    for file in files.iter() {
+        if let Some(name) = file.path.file_name() {
+            builder.append_path_with_name(&file.path, name)?;
+        }
+    }
+    let archive_file = builder.into_inner()?.finish()?;
+
+    // 保持期間の判定はまとめたファイルの更新日時に従う
+    if let Some(last) = files.last() {
+        archive_file.as_file().set_modified(last.modified.into())?;
+    }
+    archive_file.commit()?;
+
+    for file in files.iter() {
+        std::fs::remove_file(&file.path)?;
+    }
+    Ok(())
+}
+
+impl RetentionPolicy {
+    /// 何も行わない方針かどうか
+    pub fn is_noop(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// ディレクトリ内のパターンに一致するファイルに方針を適用する．
+    pub(crate) fn apply(
+        &self,
+        dir_path: &Path,
+        pattern: &SavedFilePattern,
+        now: DateTime<Local>,
+    ) -> Result<(), Error> {
+        if self.is_noop() {
+            return Ok(());
+        }
+
+        if self.daily_rollup {
+            let mut daily_files = BTreeMap::<NaiveDate, Vec<SavedFile>>::new();
+            for file in saved_files(dir_path, pattern)? {
+                let date = file.modified.date_naive();
+                if date < now.date_naive() && !file.is_archive {
+                    daily_files.entry(date).or_default().push(file);
+                }
+            }
+
+            for (date, files) in daily_files.iter() {
+                let archive_path = archive_path(dir_path, &pattern.archive_prefix, *date);
+                info!("Archiving {} files into: {:?}", files.len(), archive_path);
+                archive(&archive_path, files)?;
+            }
+        }
+
+        let mut files = saved_files(dir_path, pattern)?;
+        let mut removed = Vec::new();
+
+        if let Some(max_age_days) = self.max_age_days {
+            let threshold = now - chrono::Duration::days(max_age_days as i64);
+            let expired = files.partition_point(|file| file.modified < threshold);
+            removed.extend(files.drain(..expired));
+        }
+        if let Some(max_files) = self.max_files {
+            let excess = files.len().saturating_sub(max_files);
+            removed.extend(files.drain(..excess));
+        }
+        if let Some(max_total_bytes) = self.max_total_bytes {
+            let mut total_bytes = files.iter().map(|file| file.size).sum::<u64>();
+            let excess = files
+                .iter()
+                .take_while(|file| {
+                    let over = total_bytes > max_total_bytes;
+                    total_bytes -= file.size;
+                    over
+                })
+                .count();
+            removed.extend(files.drain(..excess));
+        }
+
+        for file in removed.iter() {
+            info!("Removing by retention policy: {:?}", file.path);
+            std::fs::remove_file(&file.path)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RetentionPolicy, SavedFilePattern};
+
+    use chrono::{DateTime, Local, TimeZone};
+    use flate2::read::GzDecoder;
+    use std::fs::File;
+    use std::path::Path;
+
+    fn local(day: u32, hour: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2023, 11, day, hour, 0, 0).unwrap()
+    }
+
+    /// 更新日時を指定してファイルを作製する．
+    fn create_file(dir_path: &Path, name: &str, size: usize, modified: DateTime<Local>) {
+        std::fs::write(dir_path.join(name), vec![b'x'; size]).unwrap();
+        File::options()
+            .write(true)
+            .open(dir_path.join(name))
+            .unwrap()
+            .set_modified(modified.into())
+            .unwrap();
+    }
+
+    fn file_names(dir_path: &Path) -> Vec<String> {
+        let mut names = std::fs::read_dir(dir_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_retention_policy() {
+        let dir_path = std::env::temp_dir().join(format!(
+            "search_and_report_retention_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir_path).unwrap();
+
+        create_file(&dir_path, "job_2023_11_20_10.json", 10, local(20, 10));
+        create_file(&dir_path, "job_2023_11_26_10.json", 10, local(26, 10));
+        create_file(&dir_path, "job_2023_11_26_11.json", 10, local(26, 11));
+        create_file(&dir_path, "job_2023_11_27_09.json", 10, local(27, 9));
+        create_file(&dir_path, "job_2023_11_27_10.json", 10, local(27, 10));
+        // 他のジョブ・リポーターのファイルや設定ファイルは対象としない
+        create_file(&dir_path, "config.json", 10, local(1, 0));
+        create_file(&dir_path, "job_2023_11_20_10.csv", 10, local(20, 10));
+        create_file(&dir_path, "job_x_2023_11_20_10.json", 10, local(20, 10));
+        create_file(&dir_path, "archive_2023_11_20.tar.gz", 10, local(20, 10));
+
+        let pattern = SavedFilePattern::new("job_%Y_%m_%d_%H", "json");
+        let policy = RetentionPolicy {
+            max_age_days: Some(5),
+            max_files: Some(3),
+            daily_rollup: true,
+            ..Default::default()
+        };
+        policy.apply(&dir_path, &pattern, local(27, 12)).unwrap();
+
+        // 20日のアーカイブは期限切れとなる
+        assert_eq!(
+            file_names(&dir_path),
+            vec![
+                "archive_2023_11_20.tar.gz",
+                "config.json",
+                "job_2023_11_20_10.csv",
+                "job_2023_11_27_09.json",
+                "job_2023_11_27_10.json",
+                "job_archive_2023_11_26.tar.gz",
+                "job_x_2023_11_20_10.json",
+            ]
+        );
+        let mut archive = tar::Archive::new(GzDecoder::new(
+            File::open(dir_path.join("job_archive_2023_11_26.tar.gz")).unwrap(),
+        ));
+        let archived = archive
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().display().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            archived,
+            vec!["job_2023_11_26_10.json", "job_2023_11_26_11.json"]
+        );
+
+        // 合計サイズは新しいものから数える
+        let policy = RetentionPolicy {
+            max_total_bytes: Some(20),
+            ..Default::default()
+        };
+        policy.apply(&dir_path, &pattern, local(27, 12)).unwrap();
+        assert_eq!(
+            file_names(&dir_path),
+            vec![
+                "archive_2023_11_20.tar.gz",
+                "config.json",
+                "job_2023_11_20_10.csv",
+                "job_2023_11_27_09.json",
+                "job_2023_11_27_10.json",
+                "job_x_2023_11_20_10.json",
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir_path).unwrap();
+    }
+}
@@ -0,0 +1,293 @@
+use super::ReportContext;
+use crate::predicates::Verdict;
+use crate::Report;
+use crate::{error::Error, Clock, Posts};
+
+use rusqlite::{params, Connection};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+/// テーブルの定義．ポストはプラットフォームとIDの組で一意となる．
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    job_name TEXT,
+    platform TEXT NOT NULL,
+    keywords TEXT NOT NULL,
+    executed_at TEXT NOT NULL,
+    post_count INTEGER NOT NULL,
+    matched INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS posts (
+    platform TEXT NOT NULL,
+    post_id TEXT NOT NULL,
+    author TEXT NOT NULL,
+    date TEXT NOT NULL,
+    time TEXT,
+    content TEXT NOT NULL,
+    first_seen_at TEXT NOT NULL,
+    last_seen_at TEXT NOT NULL,
+    PRIMARY KEY (platform, post_id)
+);
+CREATE TABLE IF NOT EXISTS run_posts (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    platform TEXT NOT NULL,
+    post_id TEXT NOT NULL,
+    PRIMARY KEY (run_id, platform, post_id)
+);
+CREATE TABLE IF NOT EXISTS verdicts (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    name TEXT NOT NULL,
+    matched INTEGER NOT NULL,
+    PRIMARY KEY (run_id, name)
+);
+";
+
+/// 一回の検索の記録．別スレッドで書き込むために所有権を持つ．
+struct RunRecord {
+    job_name: Option<String>,
+    platform: String,
+    keywords: String,
+    executed_at: String,
+    posts: Posts,
+    verdicts: Vec<Verdict>,
+}
+
+impl RunRecord {
+    fn insert(&self, connection: &mut Connection) -> Result<(), Error> {
+        let transaction = connection.transaction()?;
+
+        transaction.execute(
+            "INSERT INTO runs (job_name, platform, keywords, executed_at, post_count, matched)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                self.job_name,
+                self.platform,
+                self.keywords,
+                self.executed_at,
+                self.posts.len() as i64,
+                self.verdicts.iter().any(|verdict| verdict.matched),
+            ],
+        )?;
+        let run_id = transaction.last_insert_rowid();
+
+        for post in self.posts.iter() {
+            transaction.execute(
+                "INSERT INTO posts (platform, post_id, author, date, time, content, first_seen_at, last_seen_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)
+                 ON CONFLICT (platform, post_id) DO UPDATE SET
+                     author = excluded.author,
+                     date = excluded.date,
+                     time = excluded.time,
+                     content = excluded.content,
+                     last_seen_at = excluded.last_seen_at",
+                params![
+                    self.platform,
+                    post.id,
+                    post.author,
+                    post.date.to_string(),
+                    post.time.map(|time| time.to_string()),
+                    post.content,
+                    self.executed_at,
+                ],
+            )?;
+            transaction.execute(
+                "INSERT OR IGNORE INTO run_posts (run_id, platform, post_id) VALUES (?1, ?2, ?3)",
+                params![run_id, self.platform, post.id],
+            )?;
+        }
+
+        for verdict in self.verdicts.iter() {
+            transaction.execute(
+                "INSERT OR REPLACE INTO verdicts (run_id, name, matched) VALUES (?1, ?2, ?3)",
+                params![run_id, verdict.name, verdict.matched],
+            )?;
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+/// 検索の度にポスト・実行・pred関数の判定結果をSQLiteに保存するリポーター．
+/// 条件の成否に関わらず記録するため，`report`では何もしない．
+pub struct SqliteReporter {
+    connection: Arc<Mutex<Connection>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl SqliteReporter {
+    /// データベースを開く．存在しない場合は作製する．
+    pub fn new<P: AsRef<Path>>(path: P, clock: Arc<dyn Clock>) -> Result<Self, Error> {
+        if let Some(dir_path) = path.as_ref().parent() {
+            if !dir_path.as_os_str().is_empty() && !dir_path.is_dir() {
+                std::fs::DirBuilder::new()
+                    .recursive(true)
+                    .create(dir_path)?;
+            }
+        }
+
+        Self::from_connection(Connection::open(path)?, clock)
+    }
+
+    fn from_connection(connection: Connection, clock: Arc<dyn Clock>) -> Result<Self, Error> {
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+            clock,
+        })
+    }
+
+    /// 保存済みのポストのID．既出のポストの除外に利用できる．
+    pub fn seen_post_ids(&self, platform: &str) -> Result<HashSet<String>, Error> {
+        let connection = self
+            .connection
+            .lock()
+            .map_err(|e| Error::DatabaseError(e.to_string()))?;
+        let mut statement = connection.prepare("SELECT post_id FROM posts WHERE platform = ?1")?;
+        let ids = statement
+            .query_map(params![platform], |row| row.get(0))?
+            .collect::<Result<HashSet<String>, _>>()?;
+
+        Ok(ids)
+    }
+}
+
+#[async_trait::async_trait]
+impl Report for SqliteReporter {
+    async fn report(&self, _context: &ReportContext<'_>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn record(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        let record = RunRecord {
+            job_name: context.job_name.map(str::to_string),
+            platform: context.platform.to_string(),
+            keywords: serde_json::to_string(context.keywords)?,
+            executed_at: self.clock.now().to_rfc3339(),
+            posts: context.posts.clone(),
+            verdicts: context.verdicts.to_vec(),
+        };
+
+        info!("Recording {} posts into database.", record.posts.len());
+        let connection = Arc::clone(&self.connection);
+        tokio::task::spawn_blocking(move || {
+            let mut connection = connection
+                .lock()
+                .map_err(|e| Error::DatabaseError(e.to_string()))?;
+            record.insert(&mut connection)
+        })
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))?
+    }
+
+    /// 書き込み中の記録を待ち，キャッシュをディスクに書き出す．
+    async fn flush(&self) -> Result<(), Error> {
+        let connection = Arc::clone(&self.connection);
+        tokio::task::spawn_blocking(move || {
+            let connection = connection
+                .lock()
+                .map_err(|e| Error::DatabaseError(e.to_string()))?;
+            Ok(connection.cache_flush()?)
+        })
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))?
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SqliteReporter;
+    use crate::clock::FixedClock;
+    use crate::predicates::Verdict;
+    use crate::reporter::ReportContext;
+    use crate::{Post, Report};
+
+    use chrono::{Duration, NaiveDate, TimeZone, Utc};
+    use rusqlite::Connection;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sqlite_reporter() {
+        let clock = Arc::new(FixedClock::new(
+            Utc.with_ymd_and_hms(2023, 11, 27, 3, 0, 0).unwrap(),
+        ));
+        let reporter =
+            SqliteReporter::from_connection(Connection::open_in_memory().unwrap(), clock.clone())
+                .unwrap();
+
+        let post = Post {
+            id: "1729".to_string(),
+            author: "alice".to_string(),
+            date: NaiveDate::from_ymd_opt(2023, 11, 27).unwrap(),
+            time: None,
+            content: "Rust CLI".to_string(),
+        };
+        let keywords = vec!["Rust".to_string()];
+        let verdicts = vec![Verdict {
+            name: "condition_contain".to_string(),
+            matched: false,
+        }];
+
+        let posts = vec![post.clone()];
+        let context = ReportContext {
+            platform: "yahoojp",
+            keywords: &keywords,
+            verdicts: &verdicts,
+            ..ReportContext::from_posts(&posts)
+        };
+        reporter.record(&context).await.unwrap();
+
+        // 同じポストは更新される
+        clock.advance(Duration::hours(1));
+        let posts = vec![
+            Post {
+                content: "Rust CLI (edited)".to_string(),
+                ..post.clone()
+            },
+            Post {
+                id: "1730".to_string(),
+                ..post
+            },
+        ];
+        reporter
+            .record(&ReportContext {
+                posts: &posts,
+                ..context
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            reporter.seen_post_ids("yahoojp").unwrap(),
+            ["1729".to_string(), "1730".to_string()]
+                .into_iter()
+                .collect()
+        );
+        assert!(reporter.seen_post_ids("other").unwrap().is_empty());
+
+        let connection = reporter.connection.lock().unwrap();
+        let (content, first_seen_at, last_seen_at): (String, String, String) = connection
+            .query_row(
+                "SELECT content, first_seen_at, last_seen_at FROM posts WHERE post_id = '1729'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(content, "Rust CLI (edited)");
+        assert_eq!(first_seen_at, "2023-11-27T03:00:00+00:00");
+        assert_eq!(last_seen_at, "2023-11-27T04:00:00+00:00");
+
+        let counts: Vec<(i64, i64)> = connection
+            .prepare("SELECT post_count, (SELECT COUNT(*) FROM verdicts WHERE run_id = runs.id) FROM runs ORDER BY id")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(counts, vec![(1, 1), (2, 1)]);
+    }
+}
@@ -0,0 +1,231 @@
+use super::{file_name_safe, latest_post, ReportContext};
+use crate::error::Error;
+use crate::predicates::Verdict;
+use crate::{Post, Posts};
+
+use chrono::NaiveDateTime;
+use minijinja::{escape_formatter, Environment, Value};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+/// テンプレートの描画に用いる環境．ファイル名に使えない文字を置き換える`filename`フィルターを持つ．
+static ENVIRONMENT: Lazy<Environment<'static>> = Lazy::new(|| {
+    let mut env = Environment::new();
+    env.add_filter("filename", |name: &str| file_name_safe(name));
+    env
+});
+
+/// ファイル名のテンプレートの描画に用いる環境．出力する値は全て`filename`フィルターと同様に
+/// 置き換えるため，値の`%`や`/`が書式やパスの区切りとして扱われることはない．
+static FILENAME_ENVIRONMENT: Lazy<Environment<'static>> = Lazy::new(|| {
+    let mut env = ENVIRONMENT.clone();
+    env.set_formatter(|out, state, value| {
+        escape_formatter(out, state, &Value::from(file_name_safe(&value.to_string())))
+    });
+    env
+});
+
+/// 最新のポスト
+#[derive(Serialize)]
+struct LatestPost<'a> {
+    #[serde(flatten)]
+    post: &'a Post,
+    datetime: NaiveDateTime,
+}
+
+/// テンプレートで利用できる変数
+#[derive(Serialize)]
+struct TemplateVariables<'a> {
+    job_name: Option<&'a str>,
+    platform: &'a str,
+    keywords: &'a [String],
+    posts: &'a Posts,
+    count: usize,
+    latest: Option<LatestPost<'a>>,
+    verdicts: &'a [Verdict],
+    /// 一致したpred関数の名前
+    matched: Vec<&'a str>,
+    /// 全てのポストを「アカウント名: 内容」として改行で連結したもの
+    summary: String,
+    latest_author: Option<&'a str>,
+    latest_datetime: Option<String>,
+    latest_content: Option<&'a str>,
+    /// 検索の失敗のリポートでのエラーの内容
+    error: Option<String>,
+}
+
+impl<'a> TemplateVariables<'a> {
+    fn new(context: &'a ReportContext<'a>, error: Option<&Error>) -> Self {
+        let latest = latest_post(context.posts);
+
+        Self {
+            job_name: context.job_name,
+            platform: context.platform,
+            keywords: context.keywords,
+            posts: context.posts,
+            count: context.posts.len(),
+            latest: latest.map(|(post, datetime)| LatestPost { post, datetime }),
+            verdicts: context.verdicts,
+            matched: context
+                .verdicts
+                .iter()
+                .filter(|verdict| verdict.matched)
+                .map(|verdict| verdict.name.as_str())
+                .collect(),
+            summary: context
+                .posts
+                .iter()
+                .map(|post| format!("{}: {}", post.author, post.content))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            latest_author: latest.map(|(post, _)| post.author.as_str()),
+            latest_datetime: latest.map(|(_, datetime)| datetime.to_string()),
+            latest_content: latest.map(|(post, _)| post.content.as_str()),
+            error: error.map(Error::to_string),
+        }
+    }
+}
+
+/// コンパイルしたテンプレートの環境内での名前
+const TEMPLATE_NAME: &str = "template";
+
+/// リポートの内容を描画するテンプレート．構文はjinja2に準ずる．
+#[derive(Debug, Clone)]
+pub struct ReportTemplate {
+    source: String,
+    /// コンパイルしたテンプレートのみを持つ環境
+    environment: Environment<'static>,
+}
+
+impl PartialEq for ReportTemplate {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for ReportTemplate {}
+
+impl ReportTemplate {
+    /// テンプレートをコンパイルする．構文が誤っている場合はエラーとなる．
+    pub fn new<S: Into<String>>(source: S) -> Result<Self, Error> {
+        Self::with_environment(source.into(), &ENVIRONMENT)
+    }
+    /// ファイル名のテンプレートをコンパイルする．`{{ }}`で出力する値のファイル名に使えない文字は
+    /// `_`に置き換える．
+    pub fn new_filename<S: Into<String>>(source: S) -> Result<Self, Error> {
+        Self::with_environment(source.into(), &FILENAME_ENVIRONMENT)
+    }
+
+    fn with_environment(source: String, environment: &Environment<'static>) -> Result<Self, Error> {
+        let mut environment = environment.clone();
+        environment.add_template_owned(TEMPLATE_NAME, source.clone())?;
+
+        Ok(Self {
+            source,
+            environment,
+        })
+    }
+    /// テンプレートの文字列
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    fn render_variables(&self, variables: TemplateVariables) -> Result<String, Error> {
+        Ok(self
+            .environment
+            .get_template(TEMPLATE_NAME)?
+            .render(variables)?)
+    }
+    /// リポートの内容から描画する．
+    pub fn render(&self, context: &ReportContext) -> Result<String, Error> {
+        self.render_variables(TemplateVariables::new(context, None))
+    }
+    /// 検索の失敗のリポートを描画する．`error`でエラーの内容を参照できる．
+    pub fn render_failure(&self, context: &ReportContext, error: &Error) -> Result<String, Error> {
+        self.render_variables(TemplateVariables::new(context, Some(error)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ReportTemplate;
+    use crate::predicates::Verdict;
+    use crate::reporter::ReportContext;
+    use crate::Post;
+
+    use chrono::{NaiveDate, NaiveTime};
+
+    #[test]
+    fn test_render() {
+        let posts = vec![
+            Post {
+                id: "1".to_string(),
+                author: "alice".to_string(),
+                date: NaiveDate::from_ymd_opt(2023, 11, 27).unwrap(),
+                time: Some(NaiveTime::from_hms_opt(12, 0, 0).unwrap()),
+                content: "Rust CLI".to_string(),
+            },
+            Post {
+                id: "2".to_string(),
+                author: "bob".to_string(),
+                date: NaiveDate::from_ymd_opt(2023, 11, 27).unwrap(),
+                time: None,
+                content: "Rust TUI".to_string(),
+            },
+        ];
+        let keywords = vec!["Rust".to_string()];
+        let verdicts = vec![
+            Verdict {
+                name: "condition_contain".to_string(),
+                matched: true,
+            },
+            Verdict {
+                name: "condition_n_per_h".to_string(),
+                matched: false,
+            },
+        ];
+        let context = ReportContext {
+            job_name: Some("rust"),
+            platform: "yahoojp",
+            keywords: &keywords,
+            posts: &posts,
+            verdicts: &verdicts,
+        };
+
+        let template = ReportTemplate::new(
+            "[{{ job_name }}] {{ keywords | join(' ') }}: {{ count }} posts ({{ matched | join(', ') }})
+{{ latest.author }} {{ latest.datetime }}
+{% for post in posts %}- {{ post.author }}: {{ post.content }}
+{% endfor %}",
+        )
+        .unwrap();
+
+        assert_eq!(
+            template.render(&context).unwrap(),
+            "[rust] Rust: 2 posts (condition_contain)
+alice 2023-11-27T12:00:00
+- alice: Rust CLI
+- bob: Rust TUI
+"
+        );
+
+        let template = ReportTemplate::new("{{ job_name }}: {{ count }} {{ error }}").unwrap();
+        let error = crate::error::Error::BlockedPageError("captcha".to_string());
+        assert_eq!(
+            template.render_failure(&context, &error).unwrap(),
+            "rust: 2 Error::BlockedPageError: captcha"
+        );
+
+        let template = ReportTemplate::new("{{ 'rust/cli 50%' | filename }}").unwrap();
+        assert_eq!(template.render(&context).unwrap(), "rust_cli_50_");
+
+        let context = ReportContext {
+            job_name: Some("../rust 100%"),
+            ..context
+        };
+        let template = ReportTemplate::new_filename("{{ job_name }}_{{ count }}_%Y").unwrap();
+        assert_eq!(template.render(&context).unwrap(), ".._rust_100__2_%Y");
+
+        assert!(ReportTemplate::new("{{ count").is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use super::{ReportContext, ReportTemplate};
+use crate::error::Error;
+use crate::request::check_status;
+use crate::Report;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use reqwest::Client;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::info;
+
+/// 値がポストの配列に置き換えられる文字列かどうか
+fn is_posts_placeholder(s: &str) -> bool {
+    matches!(s.trim(), "{{posts}}" | "{{ posts }}")
+}
+
+/// 文字列をテンプレートとしてコンパイルしたjsonのテンプレート
+enum JsonTemplate {
+    /// ポストの配列に置き換えられる文字列
+    Posts,
+    String(ReportTemplate),
+    Array(Vec<JsonTemplate>),
+    Object(Vec<(String, JsonTemplate)>),
+    Other(Value),
+}
+
+impl JsonTemplate {
+    /// json中の文字列のテンプレートを全てコンパイルする．誤っている場合はエラーとなる．
+    fn new(template: &Value) -> Result<Self, Error> {
+        Ok(match template {
+            Value::String(s) if is_posts_placeholder(s) => Self::Posts,
+            Value::String(s) => Self::String(ReportTemplate::new(s.as_str())?),
+            Value::Array(values) => {
+                Self::Array(values.iter().map(Self::new).collect::<Result<_, _>>()?)
+            }
+            Value::Object(map) => Self::Object(
+                map.iter()
+                    .map(|(key, value)| Ok((key.clone(), Self::new(value)?)))
+                    .collect::<Result<_, Error>>()?,
+            ),
+            other => Self::Other(other.clone()),
+        })
+    }
+
+    /// jsonを描画する．文字列はテンプレートとして描画し，`"{{ posts }}"`のみの文字列はポストの配列に置き換える．
+    /// `error`を渡した場合は検索の失敗のリポートとして描画する．
+    fn render(&self, context: &ReportContext, error: Option<&Error>) -> Result<Value, Error> {
+        match self {
+            Self::Posts => Ok(serde_json::to_value(context.posts)?),
+            Self::String(template) => Ok(Value::String(match error {
+                Some(error) => template.render_failure(context, error)?,
+                None => template.render(context)?,
+            })),
+            Self::Array(values) => values
+                .iter()
+                .map(|value| value.render(context, error))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::Array),
+            Self::Object(entries) => entries
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), value.render(context, error)?)))
+                .collect::<Result<serde_json::Map<_, _>, Error>>()
+                .map(Value::Object),
+            Self::Other(other) => Ok(other.clone()),
+        }
+    }
+}
+
+/// 任意のURLにjsonをPOSTするリポーター．Slack, Discordなどのwebhookに利用できる．
+pub struct WebhookReporter {
+    url: String,
+    body_template: JsonTemplate,
+    failure_body_template: JsonTemplate,
+    headers: HeaderMap,
+    timeout: Duration,
+}
+
+impl WebhookReporter {
+    /// デフォルトのタイムアウト
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+    /// 検索の失敗を通知するデフォルトの文言．`failure_body`を指定しない場合はSlackの`text`とDiscordの`content`に入れる．
+    pub const DEFAULT_FAILURE_MESSAGE: &'static str =
+        "{% if job_name %}{{ job_name }}: {% endif %}Search failed. {{ error }}";
+
+    /// リポーターを作製する．`body_template`中の文字列のテンプレートが誤っている場合はエラーとなる．
+    pub fn new<S: Into<String>>(url: S, body_template: Value) -> Result<Self, Error> {
+        Ok(Self {
+            url: url.into(),
+            body_template: JsonTemplate::new(&body_template)?,
+            failure_body_template: JsonTemplate::new(&serde_json::json!({
+                "text": Self::DEFAULT_FAILURE_MESSAGE,
+                "content": Self::DEFAULT_FAILURE_MESSAGE,
+            }))?,
+            headers: HeaderMap::new(),
+            timeout: Self::DEFAULT_TIMEOUT,
+        })
+    }
+    /// リクエストのヘッダーを設定する．
+    pub fn with_headers(mut self, headers: &BTreeMap<String, String>) -> Result<Self, Error> {
+        for (name, value) in headers.iter() {
+            let name = HeaderName::try_from(name.as_str())
+                .map_err(|e| Error::RequestError(e.to_string()))?;
+            let value = HeaderValue::try_from(value.as_str())
+                .map_err(|e| Error::RequestError(e.to_string()))?;
+            self.headers.insert(name, value);
+        }
+        Ok(self)
+    }
+    /// タイムアウトを設定する．
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    /// 検索の失敗を通知するjsonのテンプレートを設定する．`{{ error }}`でエラーの内容を参照できる．
+    pub fn with_failure_body(mut self, failure_body_template: Value) -> Result<Self, Error> {
+        self.failure_body_template = JsonTemplate::new(&failure_body_template)?;
+        Ok(self)
+    }
+
+    async fn post(&self, body: &Value) -> Result<(), Error> {
+        let body = serde_json::to_vec(body)?;
+        let client = Client::builder().timeout(self.timeout).build()?;
+
+        info!("Posting webhook to {}.", self.url);
+        let res = client
+            .post(&self.url)
+            .headers(self.headers.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await?;
+        check_status(res)?;
+
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Report for WebhookReporter {
+    async fn report(&self, context: &ReportContext<'_>) -> Result<(), Error> {
+        self.post(&self.body_template.render(context, None)?).await
+    }
+
+    async fn report_failure(
+        &self,
+        context: &ReportContext<'_>,
+        error: &Error,
+    ) -> Result<(), Error> {
+        self.post(&self.failure_body_template.render(context, Some(error))?)
+            .await
+    }
+
+    fn reports_failure(&self) -> bool {
+        true
+    }
+
+    /// タイムアウトしてもPOSTが届いている可能性があるため，タイムアウトは再試行しない．
+    fn is_idempotent(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{JsonTemplate, WebhookReporter};
+    use crate::reporter::ReportContext;
+    use crate::Post;
+
+    use chrono::{NaiveDate, NaiveTime};
+    use serde_json::json;
+
+    #[test]
+    fn test_json_template() {
+        let posts = vec![
+            Post {
+                id: "1".to_string(),
+                author: "alice".to_string(),
+                date: NaiveDate::from_ymd_opt(2023, 11, 27).unwrap(),
+                time: Some(NaiveTime::from_hms_opt(12, 0, 0).unwrap()),
+                content: "Rust CLI".to_string(),
+            },
+            Post {
+                id: "2".to_string(),
+                author: "bob".to_string(),
+                date: NaiveDate::from_ymd_opt(2023, 11, 27).unwrap(),
+                time: Some(NaiveTime::from_hms_opt(11, 0, 0).unwrap()),
+                content: "Rust TUI".to_string(),
+            },
+        ];
+
+        let template = json!({
+            "text": "{{count}} posts. Latest: {{latest_author}} at {{latest_datetime}}",
+            "blocks": [{"text": "{{summary}}"}],
+            "posts": "{{ posts }}",
+            "unknown": "{{ unknown }}",
+            "number": 1
+        });
+
+        let rendered = JsonTemplate::new(&template)
+            .unwrap()
+            .render(&ReportContext::from_posts(&posts), None)
+            .unwrap();
+
+        assert_eq!(
+            rendered,
+            json!({
+                "text": "2 posts. Latest: alice at 2023-11-27 12:00:00",
+                "blocks": [{"text": "alice: Rust CLI\nbob: Rust TUI"}],
+                "posts": serde_json::to_value(&posts).unwrap(),
+                "unknown": "",
+                "number": 1
+            })
+        );
+
+        let context = ReportContext {
+            job_name: Some("rust"),
+            ..ReportContext::from_posts(&posts)
+        };
+        let error = crate::error::Error::BlockedPageError("captcha".to_string());
+        let rendered =
+            JsonTemplate::new(&json!({"text": WebhookReporter::DEFAULT_FAILURE_MESSAGE}))
+                .unwrap()
+                .render(&context, Some(&error))
+                .unwrap();
+        assert_eq!(
+            rendered,
+            json!({"text": "rust: Search failed. Error::BlockedPageError: captcha"})
+        );
+    }
+}
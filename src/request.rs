@@ -1,7 +1,91 @@
 use crate::error::Error;
 
+use chrono::{DateTime, Utc};
+use reqwest::header::RETRY_AFTER;
+use reqwest::Response;
+use std::time::Duration;
+
 /// htmlなどのソースをリクエストするためのトレイト
 #[async_trait::async_trait]
 pub trait RequestSource {
     async fn request(keywords: &[String]) -> Result<String, Error>;
 }
+
+/// Retry-Afterヘッダーで指定された再試行の時期
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryAfter {
+    /// 待機時間
+    Delay(Duration),
+    /// 日時
+    At(DateTime<Utc>),
+}
+
+impl RetryAfter {
+    /// `now`からの待機時間．過ぎた日時の場合は0とする．
+    pub fn delay(&self, now: DateTime<Utc>) -> Duration {
+        match self {
+            RetryAfter::Delay(delay) => *delay,
+            RetryAfter::At(at) => (*at - now).to_std().unwrap_or_default(),
+        }
+    }
+}
+
+/// Retry-Afterヘッダーの値を変換する．秒数とHTTP日付の両方に対応する．
+fn parse_retry_after(value: &str) -> Option<RetryAfter> {
+    if let Ok(secs) = value.trim().parse::<u64>() {
+        return Some(RetryAfter::Delay(Duration::from_secs(secs)));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    Some(RetryAfter::At(date.with_timezone(&Utc)))
+}
+
+/// レスポンスのステータスコードを確認し，成功以外の場合はエラーとする．
+pub(crate) fn check_status(res: Response) -> Result<Response, Error> {
+    let status = res.status();
+
+    if status.is_success() {
+        Ok(res)
+    } else {
+        Err(Error::HttpStatusError {
+            url: res.url().to_string(),
+            status: status.as_u16(),
+            retry_after: res
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_retry_after),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_retry_after, RetryAfter};
+
+    use chrono::{TimeZone, Utc};
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(
+            parse_retry_after("120"),
+            Some(RetryAfter::Delay(Duration::from_secs(120)))
+        );
+
+        let retry_after = parse_retry_after("Mon, 27 Nov 2023 03:00:00 GMT").unwrap();
+        let at = Utc.with_ymd_and_hms(2023, 11, 27, 3, 0, 0).unwrap();
+        assert_eq!(retry_after, RetryAfter::At(at));
+        // 日時は現在時刻からの待機時間に変換する
+        assert_eq!(
+            retry_after.delay(at - chrono::Duration::seconds(30)),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            retry_after.delay(at + chrono::Duration::seconds(30)),
+            Duration::ZERO
+        );
+
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
@@ -0,0 +1,216 @@
+use crate::error::Error;
+use crate::Clock;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tracing::warn;
+
+/// 一時的なエラーに対する再試行の設定．待機時間は指数関数的に増加し，ジッターが加えられる．
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 再試行の最大回数
+    pub max_retries: u32,
+    /// 初回の待機時間(ミリ秒)
+    pub initial_delay_ms: u64,
+    /// 待機時間の上限(ミリ秒)
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_delay_ms: 1000,
+            max_delay_ms: 60_000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 再試行しない
+    pub const NONE: RetryPolicy = RetryPolicy {
+        max_retries: 0,
+        initial_delay_ms: 0,
+        max_delay_ms: 0,
+    };
+
+    /// `attempt`回目(0始まり)の失敗の後の待機時間．半分を固定，残りをランダムとする．
+    /// サーバーから待機時間が指定された場合はそれより短くしない．
+    /// 指定された待機時間が上限を超える場合は再試行しないものとして`None`を返す．
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        let base_ms = self
+            .initial_delay_ms
+            .saturating_mul(2_u64.saturating_pow(attempt))
+            .min(self.max_delay_ms);
+        let jitter_ms = rand::thread_rng().gen_range(0..=base_ms / 2);
+        let delay = Duration::from_millis(base_ms - base_ms / 2 + jitter_ms);
+
+        match retry_after {
+            Some(retry_after) if retry_after > Duration::from_millis(self.max_delay_ms) => None,
+            Some(retry_after) => Some(delay.max(retry_after)),
+            None => Some(delay),
+        }
+    }
+
+    /// 一時的なエラーの場合に再試行しながら`f`を実行する．
+    pub async fn run<T, F, Fut>(&self, name: &str, clock: &dyn Clock, f: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        self.run_if(name, clock, Error::is_retryable, f).await
+    }
+
+    /// `is_retryable`が真となるエラーの場合に再試行しながら`f`を実行する．
+    pub async fn run_if<T, F, Fut, P>(
+        &self,
+        name: &str,
+        clock: &dyn Clock,
+        is_retryable: P,
+        mut f: F,
+    ) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+        P: Fn(&Error) -> bool,
+    {
+        let mut attempt = 0;
+
+        loop {
+            match f().await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < self.max_retries && is_retryable(&e) => {
+                    let Some(delay) = self.delay(attempt, e.retry_after(clock.now())) else {
+                        warn!(
+                            "{} failed, not retrying as the server asked to wait longer than {}ms. {}",
+                            name, self.max_delay_ms, e
+                        );
+                        return Err(e);
+                    };
+                    attempt += 1;
+                    warn!(
+                        "{} failed, retrying in {:?} ({}/{}). {}",
+                        name, delay, attempt, self.max_retries, e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RetryPolicy;
+    use crate::clock::{FixedClock, SystemClock};
+    use crate::error::Error;
+    use crate::request::RetryAfter;
+
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    const POLICY: RetryPolicy = RetryPolicy {
+        max_retries: 3,
+        initial_delay_ms: 1,
+        max_delay_ms: 4,
+    };
+
+    #[test]
+    fn test_delay() {
+        for attempt in 0..10 {
+            let delay = POLICY.delay(attempt, None).unwrap();
+            let base = Duration::from_millis((1_u64 << attempt).min(4));
+            assert!(base / 2 <= delay && delay <= base, "{:?}", delay);
+        }
+        assert_eq!(
+            POLICY.delay(0, Some(Duration::from_millis(3))),
+            Some(Duration::from_millis(3))
+        );
+        // 上限を超える待機時間を指定された場合は再試行しない
+        assert_eq!(POLICY.delay(0, Some(Duration::from_secs(86400))), None);
+    }
+
+    #[tokio::test]
+    async fn test_run() {
+        // 一時的なエラーは再試行する
+        let calls = AtomicU32::new(0);
+        let res = POLICY
+            .run("test", &SystemClock, || async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(Error::TimeoutError("timeout".to_string())),
+                    1 => Err(Error::HttpStatusError {
+                        url: "http://localhost".to_string(),
+                        status: 429,
+                        retry_after: Some(RetryAfter::Delay(Duration::from_millis(1))),
+                    }),
+                    _ => Ok("ok"),
+                }
+            })
+            .await;
+        assert_eq!(res.unwrap(), "ok");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // 恒久的なエラーは再試行しない
+        let calls = AtomicU32::new(0);
+        let res: Result<(), _> = POLICY
+            .run("test", &SystemClock, || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(Error::HttpStatusError {
+                    url: "http://localhost".to_string(),
+                    status: 404,
+                    retry_after: None,
+                })
+            })
+            .await;
+        assert!(res.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // 回数の上限
+        let calls = AtomicU32::new(0);
+        let res: Result<(), _> = POLICY
+            .run("test", &SystemClock, || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(Error::ConnectionError("refused".to_string()))
+            })
+            .await;
+        assert!(res.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        // Retry-Afterの日時は時計に従い，上限を超える場合は再試行しない
+        let now = chrono::Utc::now();
+        let clock = FixedClock::new(now);
+        let calls = AtomicU32::new(0);
+        let res: Result<(), _> = POLICY
+            .run("test", &clock, || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(Error::HttpStatusError {
+                    url: "http://localhost".to_string(),
+                    status: 503,
+                    retry_after: Some(RetryAfter::At(now + chrono::Duration::days(1))),
+                })
+            })
+            .await;
+        assert!(res.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // 再試行するエラーを限定できる
+        let calls = AtomicU32::new(0);
+        let res: Result<(), _> = POLICY
+            .run_if(
+                "test",
+                &SystemClock,
+                |e| !matches!(e, Error::TimeoutError(_)),
+                || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Err(Error::TimeoutError("timeout".to_string()))
+                },
+            )
+            .await;
+        assert!(res.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
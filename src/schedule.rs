@@ -0,0 +1,104 @@
+use crate::error::Error;
+
+use chrono::{DateTime, Duration, LocalResult, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+/// 次回の実行日時を探す候補の上限
+const MAX_CANDIDATES: usize = 1000;
+
+/// タイムゾーンを考慮したcronのスケジュール．
+/// 夏時間の移行で存在しない時刻は移行直後に，重複する時刻は一回目にのみ実行する．
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    schedule: cron::Schedule,
+    timezone: Tz,
+}
+
+impl CronSchedule {
+    /// cronの構文(秒 分 時 日 月 曜日 年)からスケジュールを作製する．
+    pub fn new(expression: &str, timezone: Tz) -> Result<Self, Error> {
+        let schedule = cron::Schedule::from_str(expression)
+            .map_err(|e| Error::ScheduleError(format!("{}: {}", expression, e)))?;
+
+        Ok(Self { schedule, timezone })
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    /// 壁時計の時刻を実際の日時に変換する．存在しない時刻は次に存在する時刻とする．
+    fn resolve(&self, wall: NaiveDateTime) -> Option<DateTime<Utc>> {
+        (0..=24 * 60)
+            .map(|minutes| wall + Duration::minutes(minutes))
+            .find_map(|wall| match self.timezone.from_local_datetime(&wall) {
+                LocalResult::Single(datetime) => Some(datetime),
+                LocalResult::Ambiguous(earliest, _) => Some(earliest),
+                LocalResult::None => None,
+            })
+            .map(|datetime| datetime.with_timezone(&Utc))
+    }
+
+    /// `now`より後の次回の実行日時
+    pub fn next_after(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        // 壁時計の時刻をUTCとして評価することで，cronの評価を夏時間から切り離す
+        let wall_now = Utc.from_utc_datetime(&now.with_timezone(&self.timezone).naive_local());
+
+        self.schedule
+            .after(&wall_now)
+            .take(MAX_CANDIDATES)
+            .filter_map(|wall| self.resolve(wall.naive_utc()))
+            .find(|next| *next > now)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CronSchedule;
+
+    use chrono::{TimeZone, Utc};
+    use chrono_tz::{America::New_York, Asia::Tokyo};
+
+    #[test]
+    fn test_next_after() {
+        // 東京時間の6時・12時
+        let schedule = CronSchedule::new("0 0 6,12 * * * *", Tokyo).unwrap();
+        let now = Utc.with_ymd_and_hms(2023, 11, 27, 0, 0, 0).unwrap();
+        assert_eq!(
+            schedule.next_after(now),
+            Some(Utc.with_ymd_and_hms(2023, 11, 27, 3, 0, 0).unwrap())
+        );
+        assert_eq!(
+            schedule.next_after(Utc.with_ymd_and_hms(2023, 11, 27, 3, 0, 0).unwrap()),
+            Some(Utc.with_ymd_and_hms(2023, 11, 27, 21, 0, 0).unwrap())
+        );
+
+        // 夏時間の開始で存在しない2:30は3:00(EDT)に実行する
+        let schedule = CronSchedule::new("0 30 2 * * * *", New_York).unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 3, 10, 0, 0, 0).unwrap();
+        assert_eq!(
+            schedule.next_after(now),
+            Some(Utc.with_ymd_and_hms(2024, 3, 10, 7, 0, 0).unwrap())
+        );
+        // 翌日は通常通り2:30(EDT)
+        assert_eq!(
+            schedule.next_after(Utc.with_ymd_and_hms(2024, 3, 10, 7, 0, 0).unwrap()),
+            Some(Utc.with_ymd_and_hms(2024, 3, 11, 6, 30, 0).unwrap())
+        );
+
+        // 夏時間の終了で重複する1:30は一回目(EDT)のみ実行する
+        let schedule = CronSchedule::new("0 30 1 * * * *", New_York).unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 11, 3, 0, 0, 0).unwrap();
+        assert_eq!(
+            schedule.next_after(now),
+            Some(Utc.with_ymd_and_hms(2024, 11, 3, 5, 30, 0).unwrap())
+        );
+        assert_eq!(
+            schedule.next_after(Utc.with_ymd_and_hms(2024, 11, 3, 5, 30, 0).unwrap()),
+            Some(Utc.with_ymd_and_hms(2024, 11, 4, 6, 30, 0).unwrap())
+        );
+
+        assert!(CronSchedule::new("every day", Tokyo).is_err());
+    }
+}